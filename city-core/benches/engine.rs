@@ -0,0 +1,114 @@
+// Move generation, evaluation and search on opening, middle game and endgame
+// positions of several board sizes.
+//
+//     cargo bench [--bench engine] [FILTER]
+//
+// The later positions come from a seeded self-play game at depth 1, so every
+// run measures the same positions.
+
+use city_core::{Game, Position, SearchLimits};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+
+const SIZES: [(i32, i32); 4] = [(5, 5), (7, 7), (9, 9), (13, 13)];
+const SEARCH_DEPTH: i32 = 4;
+
+// (name, position) for the opening, middle game and endgame of a board size,
+// taken a third and two thirds into a game the engine plays against itself
+fn positions(width: i32, height: i32) -> Vec<(String, Position)> {
+    let limits = SearchLimits {
+        move_time_ms: None,
+        max_depth: Some(1),
+        ..SearchLimits::default()
+    };
+    let mut game = Game::new(width, height);
+    game.parallel_search = false;
+    game.seed = Some(0);
+    let mut played = Vec::new();
+    while !game.game_over() {
+        played.push(game.to_position());
+        let best = game.iterative_deepening_minimax(&limits).best_move;
+        game.make_move(best, true, true)
+            .expect("the engine plays legal moves");
+    }
+
+    let plies = played.len();
+    [
+        ("opening", 0),
+        ("middle", plies / 3),
+        ("endgame", 2 * plies / 3),
+    ]
+    .into_iter()
+    .map(|(phase, ply)| {
+        (
+            format!("{}x{} {}", width, height, phase),
+            played[ply].clone(),
+        )
+    })
+    .collect()
+}
+
+fn all_positions() -> Vec<(String, Position)> {
+    SIZES.iter().flat_map(|&(w, h)| positions(w, h)).collect()
+}
+
+fn move_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("possible_moves");
+    for (name, position) in all_positions() {
+        let mut game = Game::from_position(position);
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| game.possible_moves())
+        });
+    }
+    group.finish();
+}
+
+fn evaluation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("territory_difference");
+    for (name, position) in all_positions() {
+        let mut game = Game::from_position(position);
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| game.territory_difference())
+        });
+    }
+    group.finish();
+}
+
+fn game_end(c: &mut Criterion) {
+    let mut group = c.benchmark_group("game_over");
+    for (name, position) in all_positions() {
+        let mut game = Game::from_position(position);
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| game.game_over())
+        });
+    }
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let limits = SearchLimits {
+        move_time_ms: None,
+        max_depth: Some(SEARCH_DEPTH),
+        ..SearchLimits::default()
+    };
+    let mut group = c.benchmark_group("iterative_deepening_minimax");
+    group.sample_size(10);
+    for (name, position) in all_positions() {
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            // a new game every time, so no tables carry over from the last run
+            b.iter_batched(
+                || {
+                    let mut game = Game::from_position(position.clone());
+                    game.parallel_search = false;
+                    game.seed = Some(0);
+                    game
+                },
+                |mut game| game.iterative_deepening_minimax(&limits),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, move_generation, evaluation, game_end, search);
+criterion_main!(benches);
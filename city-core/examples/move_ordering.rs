@@ -0,0 +1,69 @@
+// Nodes searched to a fixed depth with the static one-ply ordering and with
+// killer/history ordering, on a few opening and middle game positions.
+//
+//     cargo run --release --example move_ordering [DEPTH]
+
+use std::time::Instant;
+
+use city_core::{Game, MoveOrdering, SearchLimits};
+
+const POSITIONS: [&str; 5] = [
+    "7x7 a1 g7 7/7/7/7/7/7 6/6/6/6/6/6/6 b",
+    "7x7 e5 c4 2b4/4b2/3b3/3b3/2g2g1/7 6/6/1g4/2gg2/4b1/6/6 b",
+    "7x7 b5 e2 2b1g2/1b1bb2/2bb3/3g3/2gg1g1/7 6/6/b2g2/bgbgg1/b3g1/6/6 b",
+    "9x9 a1 i9 9/9/9/9/9/9/9/9 8/8/8/8/8/8/8/8/8 b",
+    "9x9 f3 f6 9/5b3/9/9/3ggg3/5g3/9/9 8/8/b7/2b2b2/3bbg2/8/7g/8/8 b",
+];
+
+fn main() {
+    let depth = std::env::args()
+        .nth(1)
+        .map(|d| d.parse().expect("depth must be a number"))
+        .unwrap_or(4);
+    let limits = SearchLimits {
+        move_time_ms: None,
+        max_depth: Some(depth),
+        ..SearchLimits::default()
+    };
+
+    println!("depth {}", depth);
+    println!(
+        "{:>12} {:>8} {:>12} {:>8} {:>8}",
+        "static", "ms", "heuristic", "ms", "nodes"
+    );
+    let mut totals = [0u64; 2];
+    for position in POSITIONS {
+        let mut counts = [(0u64, 0u128); 2];
+        for (i, ordering) in [MoveOrdering::Static, MoveOrdering::Heuristic]
+            .into_iter()
+            .enumerate()
+        {
+            let mut game = Game::from_position_string(position).expect("valid position");
+            game.parallel_search = false; // node counts are only repeatable single threaded
+            game.move_ordering = ordering;
+            let start = Instant::now();
+            let result = game.iterative_deepening_minimax(&limits);
+            counts[i] = (result.nodes, start.elapsed().as_millis());
+            totals[i] += result.nodes;
+        }
+        println!(
+            "{:>12} {:>8} {:>12} {:>8} {:>+7.1}%  {}",
+            counts[0].0,
+            counts[0].1,
+            counts[1].0,
+            counts[1].1,
+            change(counts[0].0, counts[1].0),
+            position
+        );
+    }
+    println!(
+        "total {} -> {} nodes ({:+.1}%)",
+        totals[0],
+        totals[1],
+        change(totals[0], totals[1])
+    );
+}
+
+fn change(before: u64, after: u64) -> f64 {
+    (after as f64 / before as f64 - 1.0) * 100.0
+}
@@ -0,0 +1,69 @@
+// Plain alpha-beta against principal variation search and late move
+// reductions, alone and together: nodes and score at a fixed depth, then the
+// depth reached in a fixed time. The default is late move reductions alone.
+//
+//     cargo run --release --example reductions [DEPTH] [MS]
+
+use std::time::Instant;
+
+use city_core::{Game, SearchLimits};
+
+const POSITIONS: [&str; 4] = [
+    "7x7 e5 c4 2b4/4b2/3b3/3b3/2g2g1/7 6/6/1g4/2gg2/4b1/6/6 b",
+    "7x7 b5 e2 2b1g2/1b1bb2/2bb3/3g3/2gg1g1/7 6/6/b2g2/bgbgg1/b3g1/6/6 b",
+    "9x9 f3 f6 9/5b3/9/9/3ggg3/5g3/9/9 8/8/b7/2b2b2/3bbg2/8/7g/8/8 b",
+    "11x11 d4 h8 11/11/11/4b6/11/11/6g4/11/11/11 10/10/10/10/10/10/10/10/10/10/10 b",
+];
+
+// (name, principal variation search, late move reductions)
+const CONFIGS: [(&str, bool, bool); 4] = [
+    ("alpha-beta", false, false),
+    ("pvs", true, false),
+    ("lmr", false, true),
+    ("pvs+lmr", true, true),
+];
+
+fn search(position: &str, pvs: bool, lmr: bool, limits: &SearchLimits) -> (i32, i32, u64, u128) {
+    let mut game = Game::from_position_string(position).expect("valid position");
+    game.parallel_search = false; // node counts are only repeatable single threaded
+    game.principal_variation_search = pvs;
+    game.late_move_reductions = lmr;
+    let start = Instant::now();
+    let result = game.iterative_deepening_minimax(limits);
+    (
+        result.depth,
+        result.score,
+        result.nodes,
+        start.elapsed().as_millis(),
+    )
+}
+
+fn main() {
+    let mut args = std::env::args()
+        .skip(1)
+        .map(|a| a.parse().expect("numeric argument"));
+    let depth = args.next().unwrap_or(4) as i32;
+    let move_time = args.next().unwrap_or(2000);
+
+    let fixed_depth = SearchLimits {
+        move_time_ms: None,
+        max_depth: Some(depth),
+        ..SearchLimits::default()
+    };
+    let fixed_time = SearchLimits {
+        move_time_ms: Some(move_time),
+        ..SearchLimits::default()
+    };
+
+    for position in POSITIONS {
+        println!("{}", position);
+        for (name, pvs, lmr) in CONFIGS {
+            let (_, score, nodes, ms) = search(position, pvs, lmr, &fixed_depth);
+            let (reached, _, _, _) = search(position, pvs, lmr, &fixed_time);
+            println!(
+                "  {:<10} depth {} score {:>3} nodes {:>9} {:>6} ms | depth {} in {} ms",
+                name, depth, score, nodes, ms, reached, move_time
+            );
+        }
+    }
+}
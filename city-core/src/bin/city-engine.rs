@@ -0,0 +1,263 @@
+// Line based engine protocol on stdin/stdout, modelled on UCI, so tournament
+// managers and other programs can drive the engine.
+//
+// Commands:
+//     city                              answered with id lines and `cityok`
+//     isready                           answered with `readyok`
+//     newgame [W H]                     start a new W x H game (7x7 by default, at most 256 a side)
+//     position startpos [moves M ...]   initial position of the current size, then moves
+//     position moves M ...              same as `position startpos moves M ...`
+//     position fen <position> [moves M ...]
+//                                       six field position string, then moves
+//     go [depth D] [movetime MS] [nodes N] [btime MS] [gtime MS] [binc MS] [ginc MS]
+//        [multipv N] [seed N] [infinite]
+//     stop                              stop the search, `bestmove` follows
+//     weights FILE                      evaluation weights from a JSON file, kept over new games
+//     perft D                           count the move sequences of D moves, by first move
+//     d                                 print the current position string
+//     quit
+//
+// While searching the engine prints one line per completed depth
+//     info depth 4 score 13 nodes 73264 time 500 nps 146528 pv c4D e5L d4R f5U
+// (one line per move with `multipv N`, each tagged `multipv 1`, `multipv 2`, ...)
+// and finally `bestmove c4D` (`bestmove none` once the game is over).
+// Scores are territory counts from the point of view of the side to move, in
+// the units of the evaluation weights after `weights`,
+// finished games score beyond +-1000000 (see `city_core::search::terminal_score`).
+// A solved endgame is followed by e.g. `info string proven Blue wins by 4`.
+// `bestmove` comes after `info string seed N`, `go seed N` repeats the search's random choices.
+
+use std::io::{self, BufRead};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use city_core::game::line_notation;
+use city_core::position::parse_board_size;
+use city_core::{EvalWeights, Game, Move, SearchControl, SearchLimits};
+
+struct Engine {
+    width: i32,
+    height: i32,
+    game: Game,
+    weights: EvalWeights,
+    search: Option<(Arc<SearchControl>, JoinHandle<()>)>,
+}
+
+impl Engine {
+    fn new() -> Engine {
+        Engine {
+            width: 7,
+            height: 7,
+            game: Game::new(7, 7),
+            weights: EvalWeights::default(),
+            search: None,
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some((control, handle)) = self.search.take() {
+            control.stop();
+            handle.join().expect("search thread panicked");
+        }
+    }
+
+    fn wait(&mut self) {
+        if let Some((_, handle)) = self.search.take() {
+            handle.join().expect("search thread panicked");
+        }
+    }
+
+    fn newgame(&mut self, args: &[&str]) -> Result<(), String> {
+        if let [width, height] = args {
+            let size = parse_board_size(&format!("{}x{}", width, height));
+            let Some((width, height)) = size else {
+                return Err(format!("bad board size '{} {}'", width, height));
+            };
+            self.width = width;
+            self.height = height;
+        } else if !args.is_empty() {
+            return Err("usage: newgame [W H]".to_string());
+        }
+        self.game = Game::new(self.width, self.height);
+        self.game.eval_weights = self.weights.clone();
+        Ok(())
+    }
+
+    fn position(&mut self, args: &[&str]) -> Result<(), String> {
+        let moves_at = args.iter().position(|&a| a == "moves");
+        let (setup, moves) = match moves_at {
+            Some(i) => (&args[..i], &args[i + 1..]),
+            None => (args, &[][..]),
+        };
+
+        let mut game = match setup {
+            [] | ["startpos"] => Game::new(self.width, self.height),
+            ["fen", fields @ ..] => {
+                Game::from_position_string(&fields.join(" ")).map_err(|e| e.to_string())?
+            }
+            _ => return Err(format!("bad position command '{}'", args.join(" "))),
+        };
+        for notation in moves {
+            Move::from_notation_on_board(notation, game.width, game.height)
+                .and_then(|mv| game.make_move(mv, true, true))
+                .map_err(|e| format!("{}: {}", notation, e))?;
+        }
+        self.width = game.width;
+        self.height = game.height;
+        game.eval_weights = self.weights.clone();
+        self.game = game;
+        Ok(())
+    }
+
+    fn go(&mut self, args: &[&str]) -> Result<(), String> {
+        // without a time, depth or node limit the search runs until `stop`
+        let mut limits = SearchLimits {
+            move_time_ms: None,
+            ..SearchLimits::default()
+        };
+        let (mut btime, mut gtime, mut binc, mut ginc) = (None, None, 0, 0);
+        let mut seed = None;
+
+        let mut args = args.iter();
+        while let Some(&name) = args.next() {
+            if name == "infinite" {
+                continue;
+            }
+            let value = args
+                .next()
+                .and_then(|v| v.parse::<u64>().ok())
+                .ok_or_else(|| format!("go {} needs a number", name))?;
+            match name {
+                "depth" => limits.max_depth = Some(value as i32),
+                "movetime" => limits.move_time_ms = Some(value),
+                "nodes" => limits.max_nodes = Some(value),
+                "multipv" => limits.multi_pv = value as usize,
+                "seed" => seed = Some(value),
+                "btime" => btime = Some(value),
+                "gtime" => gtime = Some(value),
+                "binc" => binc = value,
+                "ginc" => ginc = value,
+                _ => return Err(format!("unknown go parameter '{}'", name)),
+            }
+        }
+        let blue_turn = self.game.blue_turn;
+        let (remaining, increment) = if blue_turn {
+            (btime, binc)
+        } else {
+            (gtime, ginc)
+        };
+        if remaining.is_some() {
+            limits.remaining_ms = remaining;
+            limits.increment_ms = increment;
+        }
+
+        let mut game = self.game.clone();
+        game.seed = seed;
+        if game.game_over() {
+            println!("bestmove none");
+            return Ok(());
+        }
+        let control = Arc::new(SearchControl::from_limits(&limits));
+        let search_control = Arc::clone(&control);
+        let handle = thread::spawn(move || {
+            let sign = if blue_turn { 1 } else { -1 };
+            let result = game.iterative_deepening_search(&limits, &search_control, |result| {
+                let ms = search_control.elapsed().as_millis().max(1) as u64;
+                for (i, line) in result.lines.iter().enumerate() {
+                    let multipv = match result.lines.len() {
+                        1 => String::new(),
+                        _ => format!(" multipv {}", i + 1),
+                    };
+                    println!(
+                        "info depth {}{} score {} nodes {} time {} nps {} pv {}",
+                        result.depth,
+                        multipv,
+                        line.ev * sign,
+                        result.nodes,
+                        ms,
+                        result.nodes * 1000 / ms,
+                        line_notation(&line.pv)
+                    );
+                }
+                if let Some(proven) = result.proven {
+                    println!("info string proven {}", proven);
+                }
+            });
+            println!("info string seed {}", result.seed);
+            println!("bestmove {:?}", result.best_move);
+        });
+        self.search = Some((control, handle));
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut engine = Engine::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else {
+            continue;
+        };
+
+        // only `isready` and `stop` may arrive while a search is running
+        if !matches!(command, "isready" | "stop") {
+            engine.stop();
+        }
+        let result = match command {
+            "city" => {
+                println!("id name city-core {}", env!("CARGO_PKG_VERSION"));
+                println!("cityok");
+                Ok(())
+            }
+            "isready" => {
+                println!("readyok");
+                Ok(())
+            }
+            "newgame" => engine.newgame(args),
+            "position" => engine.position(args),
+            "go" => engine.go(args),
+            "stop" => {
+                engine.stop();
+                Ok(())
+            }
+            "weights" => match args {
+                [path] => EvalWeights::load(path)
+                    .map(|weights| {
+                        engine.game.eval_weights = weights.clone();
+                        engine.weights = weights;
+                    })
+                    .map_err(|e| e.to_string()),
+                _ => Err("usage: weights FILE".to_string()),
+            },
+            "perft" => match args {
+                [depth] => depth
+                    .parse()
+                    .map(|depth| {
+                        let divide = engine.game.perft_divide(depth);
+                        for (mv, count) in &divide {
+                            println!("{:?}: {}", mv, count);
+                        }
+                        let total: u64 = divide.iter().map(|(_, count)| count).sum();
+                        println!("nodes {}", total);
+                    })
+                    .map_err(|_| format!("bad depth '{}'", depth)),
+                _ => Err("usage: perft D".to_string()),
+            },
+            "d" => {
+                println!("{}", engine.game.to_position_string());
+                Ok(())
+            }
+            "quit" => {
+                engine.stop();
+                return;
+            }
+            _ => Err(format!("unknown command '{}'", command)),
+        };
+        if let Err(err) = result {
+            println!("info string error: {}", err);
+        }
+    }
+    // end of input, let a running search finish and print its move
+    engine.wait();
+}
@@ -0,0 +1,528 @@
+// Engine against engine, to tell whether a change made the bot stronger.
+//
+//     city-match [--games N] [--size 7x7] [--openings PLIES] [--seed N]
+//                [--elo0 E] [--elo1 E] [--quiet] <ENGINE A> <ENGINE B>
+//
+// An engine is a comma separated list of settings, `time=100` when empty:
+//     time=MS depth=D nodes=N         search limits (nodes are iterations for mcts)
+//     engine=minimax|mcts             search algorithm
+//     level=beginner|...|expert       play `Game::bot_move` at this level instead
+//     weights=FILE                    evaluation weights
+//     ordering=static|heuristic pvs=on|off lmr=on|off solver=on|off
+//     playout=random|greedy|evaluation exploration=C   with engine=mcts only
+// e.g. `city-match --games 200 "time=100" "time=100,weights=tuned.json"`.
+//
+// Every opening of `--openings` random plies is played twice with the colors
+// swapped. The report counts wins, draws and losses of engine A, estimates the
+// Elo difference of A over B with a 95% interval and runs a sequential
+// probability ratio test of elo0 (default 0) against elo1 (default 10), which
+// ends the match before `--games` once it accepts either hypothesis.
+
+use std::process;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use city_core::game::line_notation;
+use city_core::position::parse_board_size;
+use city_core::{
+    Difficulty, EvalWeights, Game, MctsConfig, Move, MoveOrdering, PlayoutPolicy, SearchLimits,
+    Winner,
+};
+
+const USAGE: &str = "usage:
+  city-match [--games N] [--size WxH] [--openings PLIES] [--seed N]
+             [--elo0 E] [--elo1 E] [--quiet] <ENGINE A> <ENGINE B>";
+
+// error rates of the sequential probability ratio test
+const SPRT_ALPHA: f64 = 0.05;
+const SPRT_BETA: f64 = 0.05;
+const CONFIDENCE_Z: f64 = 1.96; // 95% interval
+
+#[derive(Clone)]
+enum Algorithm {
+    Minimax,
+    Mcts(MctsConfig),
+    Level(Difficulty),
+}
+
+#[derive(Clone)]
+struct Engine {
+    name: String,
+    algorithm: Algorithm,
+    limits: SearchLimits,
+    weights: EvalWeights,
+    move_ordering: MoveOrdering,
+    principal_variation_search: bool,
+    late_move_reductions: bool,
+    endgame_solver: bool,
+}
+
+struct Options {
+    games: usize,
+    size: (i32, i32),
+    opening_plies: usize,
+    seed: Option<u64>,
+    elo0: f64,
+    elo1: f64,
+    quiet: bool,
+    engines: Vec<Engine>,
+}
+
+fn parse_switch(value: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("expected on or off, not '{}'", value)),
+    }
+}
+
+fn parse_engine(spec: &str) -> Result<Engine, String> {
+    let mut engine = Engine {
+        name: spec.to_string(),
+        algorithm: Algorithm::Minimax,
+        limits: SearchLimits {
+            move_time_ms: Some(100),
+            ..SearchLimits::default()
+        },
+        weights: EvalWeights::default(),
+        move_ordering: MoveOrdering::default(),
+        principal_variation_search: false,
+        late_move_reductions: true,
+        endgame_solver: true,
+    };
+    let mut mcts = MctsConfig::default();
+    let mut mcts_setting = None; // the first setting only mcts reads
+    let mut level = None;
+
+    for setting in spec.split(',').filter(|s| !s.is_empty()) {
+        let (key, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("setting '{}' is not key=value", setting))?;
+        let bad = || format!("bad value '{}' for {}", value, key);
+        match key {
+            "time" => engine.limits.move_time_ms = Some(value.parse().map_err(|_| bad())?),
+            "depth" => {
+                engine.limits.max_depth = Some(value.parse().map_err(|_| bad())?);
+                engine.limits.move_time_ms = None;
+            }
+            "nodes" => {
+                engine.limits.max_nodes = Some(value.parse().map_err(|_| bad())?);
+                engine.limits.move_time_ms = None;
+            }
+            "engine" => {
+                engine.algorithm = match value {
+                    "minimax" => Algorithm::Minimax,
+                    "mcts" => Algorithm::Mcts(MctsConfig::default()),
+                    _ => return Err(bad()),
+                }
+            }
+            "level" => {
+                level = Some(match value {
+                    "beginner" => Difficulty::Beginner,
+                    "easy" => Difficulty::Easy,
+                    "medium" => Difficulty::Medium,
+                    "hard" => Difficulty::Hard,
+                    "expert" => Difficulty::Expert,
+                    _ => return Err(bad()),
+                })
+            }
+            "weights" => engine.weights = EvalWeights::load(value).map_err(|e| e.to_string())?,
+            "ordering" => {
+                engine.move_ordering = match value {
+                    "static" => MoveOrdering::Static,
+                    "heuristic" => MoveOrdering::Heuristic,
+                    _ => return Err(bad()),
+                }
+            }
+            "pvs" => engine.principal_variation_search = parse_switch(value)?,
+            "lmr" => engine.late_move_reductions = parse_switch(value)?,
+            "solver" => engine.endgame_solver = parse_switch(value)?,
+            "playout" => {
+                mcts_setting.get_or_insert(key);
+                mcts.playout = match value {
+                    "random" => PlayoutPolicy::Random,
+                    "greedy" => PlayoutPolicy::Greedy,
+                    "evaluation" => PlayoutPolicy::Evaluation,
+                    _ => return Err(bad()),
+                }
+            }
+            "exploration" => {
+                mcts_setting.get_or_insert(key);
+                mcts.exploration = value.parse().map_err(|_| bad())?;
+            }
+            _ => return Err(format!("unknown setting '{}'", key)),
+        }
+    }
+
+    if let Algorithm::Mcts(config) = &mut engine.algorithm {
+        *config = mcts;
+    }
+    if let Some(level) = level {
+        engine.algorithm = Algorithm::Level(level);
+    }
+    if let Some(key) = mcts_setting
+        && !matches!(engine.algorithm, Algorithm::Mcts(_))
+    {
+        return Err(format!("{} needs engine=mcts", key));
+    }
+    if engine.name.is_empty() {
+        engine.name = "time=100".to_string();
+    }
+    Ok(engine)
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        games: 100,
+        size: (7, 7),
+        opening_plies: 4,
+        seed: None,
+        elo0: 0.0,
+        elo1: 10.0,
+        quiet: false,
+        engines: Vec::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--quiet" {
+            options.quiet = true;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            options.engines.push(parse_engine(arg)?);
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", arg))?;
+        let bad = || format!("bad value '{}' for {}", value, arg);
+        match arg.as_str() {
+            "--games" => options.games = value.parse().map_err(|_| bad())?,
+            "--size" => options.size = parse_board_size(value).ok_or_else(bad)?,
+            "--openings" => options.opening_plies = value.parse().map_err(|_| bad())?,
+            "--seed" => options.seed = Some(value.parse().map_err(|_| bad())?),
+            "--elo0" => options.elo0 = value.parse().map_err(|_| bad())?,
+            "--elo1" => options.elo1 = value.parse().map_err(|_| bad())?,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    if options.engines.len() != 2 {
+        return Err(USAGE.to_string());
+    }
+    Ok(options)
+}
+
+impl Engine {
+    // the engine's own copy of the game, with its settings and search tables
+    fn new_game(&self, options: &Options, opening: &[Move], seed: u64) -> Game {
+        let mut game = Game::new(options.size.0, options.size.1);
+        game.seed = Some(seed);
+        game.eval_weights = self.weights.clone();
+        game.move_ordering = self.move_ordering;
+        game.principal_variation_search = self.principal_variation_search;
+        game.late_move_reductions = self.late_move_reductions;
+        game.endgame_solver = self.endgame_solver;
+        for &mv in opening {
+            game.make_move(mv, true, true).expect("openings are legal");
+        }
+        game
+    }
+
+    fn choose(&self, game: &mut Game) -> Move {
+        match &self.algorithm {
+            Algorithm::Minimax => game.iterative_deepening_minimax(&self.limits).best_move,
+            Algorithm::Mcts(config) => {
+                let result = game.mcts(&self.limits, config);
+                result.expect("only running games are searched").best_move
+            }
+            Algorithm::Level(level) => game.bot_move(*level),
+        }
+    }
+}
+
+// random plies that leave the game running
+fn random_opening(options: &Options, rng: &mut StdRng) -> Vec<Move> {
+    loop {
+        let mut game = Game::new(options.size.0, options.size.1);
+        let mut opening = Vec::new();
+        while opening.len() < options.opening_plies && !game.game_over() {
+            let moves = game.possible_moves();
+            let mv = moves[rng.random_range(0..moves.len())];
+            game.make_move(mv, true, true)
+                .expect("listed moves are legal");
+            opening.push(mv);
+        }
+        if !game.game_over() {
+            return opening;
+        }
+    }
+}
+
+fn play_game(
+    options: &Options,
+    opening: &[Move],
+    blue: &Engine,
+    green: &Engine,
+    seed: u64,
+) -> Game {
+    // each engine searches its own game, so neither gets the other's table,
+    // killers and history, and both games see every move
+    let mut games = [
+        blue.new_game(options, opening, seed),
+        green.new_game(options, opening, seed),
+    ];
+    while !games[0].game_over() {
+        let (engine, game) = if games[0].blue_turn {
+            (blue, &mut games[0])
+        } else {
+            (green, &mut games[1])
+        };
+        let mv = engine.choose(game);
+        for game in &mut games {
+            game.make_move(mv, true, true)
+                .expect("the engines play legal moves");
+        }
+    }
+    let [game, _] = games;
+    game
+}
+
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+fn elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn format_elo(score: f64) -> String {
+    if score <= 0.0 {
+        "-inf".to_string()
+    } else if score >= 1.0 {
+        "+inf".to_string()
+    } else {
+        format!("{:+.1}", elo(score))
+    }
+}
+
+struct Tally {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+impl Tally {
+    fn games(&self) -> f64 {
+        (self.wins + self.draws + self.losses) as f64
+    }
+
+    fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games()
+    }
+
+    // variance of the result of a single game
+    fn variance(&self) -> f64 {
+        let s = self.score();
+        (self.wins as f64 * (1.0 - s).powi(2)
+            + self.draws as f64 * (0.5 - s).powi(2)
+            + self.losses as f64 * s.powi(2))
+            / self.games()
+    }
+
+    // log likelihood ratio of elo1 over elo0, with the game results taken
+    // as normally distributed around the score
+    fn log_likelihood_ratio(&self, elo0: f64, elo1: f64) -> Option<f64> {
+        let variance = self.variance();
+        if variance <= 0.0 {
+            return None;
+        }
+        let (s0, s1, s) = (expected_score(elo0), expected_score(elo1), self.score());
+        Some(self.games() * ((s - s0).powi(2) - (s - s1).powi(2)) / (2.0 * variance))
+    }
+
+    // the hypothesis the test accepts, once the ratio crosses a bound
+    fn accepted(&self, elo0: f64, elo1: f64) -> Option<&'static str> {
+        let (lower, upper) = sprt_bounds();
+        match self.log_likelihood_ratio(elo0, elo1)? {
+            llr if llr >= upper => Some("H1"),
+            llr if llr <= lower => Some("H0"),
+            _ => None,
+        }
+    }
+}
+
+// log likelihood ratios that end the sequential probability ratio test
+fn sprt_bounds() -> (f64, f64) {
+    let lower = (SPRT_BETA / (1.0 - SPRT_ALPHA)).ln();
+    let upper = ((1.0 - SPRT_BETA) / SPRT_ALPHA).ln();
+    (lower, upper)
+}
+
+fn report(options: &Options, tally: &Tally) {
+    let [a, b] = [&options.engines[0], &options.engines[1]];
+    println!("A: {}", a.name);
+    println!("B: {}", b.name);
+    let score = tally.score();
+    println!(
+        "{} games: A wins {}, draws {}, loses {} (score {:.1}%)",
+        tally.games(),
+        tally.wins,
+        tally.draws,
+        tally.losses,
+        score * 100.0
+    );
+
+    let margin = CONFIDENCE_Z * (tally.variance() / tally.games()).sqrt();
+    let (low, high) = (score - margin, score + margin);
+    if low > 0.0 && high < 1.0 {
+        println!(
+            "Elo difference {} ± {:.1} (95%, {} .. {})",
+            format_elo(score),
+            (elo(high) - elo(low)) / 2.0,
+            format_elo(low),
+            format_elo(high)
+        );
+    } else {
+        println!(
+            "Elo difference {} (95%, {} .. {})",
+            format_elo(score),
+            format_elo(low),
+            format_elo(high)
+        );
+    }
+
+    let (lower, upper) = sprt_bounds();
+    let (elo0, elo1) = (options.elo0, options.elo1);
+    let verdict = match tally.log_likelihood_ratio(elo0, elo1) {
+        Some(llr) => match tally.accepted(elo0, elo1) {
+            Some(hypothesis) => format!("LLR {:.2}, {} accepted", llr, hypothesis),
+            None => format!("LLR {:.2}, inconclusive", llr),
+        },
+        None => "no variance in the results, inconclusive".to_string(),
+    };
+    println!(
+        "SPRT elo0 {} elo1 {} (bounds {:.2} .. {:.2}): {}",
+        options.elo0, options.elo1, lower, upper, verdict
+    );
+}
+
+fn run(options: Options) -> Result<(), String> {
+    if options.games == 0 {
+        return Err("no games to play".to_string());
+    }
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    let [a, b] = [&options.engines[0], &options.engines[1]];
+    let mut tally = Tally {
+        wins: 0,
+        draws: 0,
+        losses: 0,
+    };
+
+    let mut opening = Vec::new();
+    for index in 0..options.games {
+        // a new opening every other game, A plays blue first
+        let a_blue = index % 2 == 0;
+        if a_blue {
+            opening = random_opening(&options, &mut rng);
+        }
+        let (blue, green) = if a_blue { (a, b) } else { (b, a) };
+        let mut game = play_game(&options, &opening, blue, green, rng.random::<u64>() >> 11);
+
+        let (winner, score) = game.game_result();
+        let a_won = match winner {
+            Winner::Draw => {
+                tally.draws += 1;
+                None
+            }
+            Winner::Blue => Some(a_blue),
+            Winner::Green => Some(!a_blue),
+        };
+        match a_won {
+            Some(true) => tally.wins += 1,
+            Some(false) => tally.losses += 1,
+            None => {}
+        }
+        if !options.quiet {
+            let outcome = match a_won {
+                Some(true) => "A wins",
+                Some(false) => "B wins",
+                None => "draw",
+            };
+            println!(
+                "game {}: A {} opening {}: {}-{}, {} (+{} ={} -{})",
+                index + 1,
+                if a_blue { "blue" } else { "green" },
+                line_notation(&opening),
+                score.blue,
+                score.green,
+                outcome,
+                tally.wins,
+                tally.draws,
+                tally.losses
+            );
+        }
+        // the test is sequential, a crossed bound ends the match
+        if tally.accepted(options.elo0, options.elo1).is_some() {
+            break;
+        }
+    }
+    report(&options, &tally);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = parse_options(&args).and_then(run) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tally(wins: usize, draws: usize, losses: usize) -> Tally {
+        Tally {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn elo_of_known_scores() {
+        assert_eq!(elo(0.5), 0.0);
+        assert_eq!(format_elo(0.75), "+190.8");
+        assert_eq!(format_elo(0.25), "-190.8");
+        assert_eq!(format_elo(0.0), "-inf");
+        assert_eq!(format_elo(1.0), "+inf");
+        for elo_difference in [-200.0, 0.0, 35.0] {
+            assert!((elo(expected_score(elo_difference)) - elo_difference).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn likelihood_ratio_favours_the_nearer_hypothesis() {
+        // about the expected scores of 0 and 10 Elo
+        let at_h0 = tally(500, 0, 500);
+        let at_h1 = tally(5144, 0, 4856);
+        assert!(at_h0.log_likelihood_ratio(0.0, 10.0).unwrap() < 0.0);
+        assert!(at_h1.log_likelihood_ratio(0.0, 10.0).unwrap() > 0.0);
+        assert_eq!(tally(3, 0, 0).log_likelihood_ratio(0.0, 10.0), None);
+    }
+
+    #[test]
+    fn the_test_stops_at_a_crossed_bound() {
+        let (lower, upper) = sprt_bounds();
+        assert!((upper - 2.944).abs() < 1e-3 && (lower + 2.944).abs() < 1e-3);
+        assert_eq!(tally(10, 10, 10).accepted(0.0, 10.0), None);
+        assert_eq!(tally(700, 0, 300).accepted(0.0, 10.0), Some("H1"));
+        assert_eq!(tally(300, 0, 700).accepted(0.0, 10.0), Some("H0"));
+        assert_eq!(tally(5000, 0, 5000).accepted(0.0, 10.0), Some("H0"));
+    }
+}
@@ -0,0 +1,302 @@
+// Texel-style tuning of the evaluation weights: the engine plays itself from
+// random openings, every position is labelled with the final result of its
+// game and the weights are fitted so a logistic of the evaluation predicts
+// those results.
+//
+//     city-tune [--games N] [--size 7x7] [--depth D] [--openings PLIES]
+//               [--weights FILE] [--steps N] [--seed N] [--out FILE]
+//
+// The games are played with the `--weights` (default territory only), which
+// are also where the fit starts. The result is scaled so territory counts one
+// point per cell and written as JSON for `city --weights` and the `weights`
+// command of `city-engine`. A fit that gives territory no positive weight is
+// not written, the games were too few to say anything.
+
+use std::process;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use city_core::position::parse_board_size;
+use city_core::search::WIN_SCORE;
+use city_core::{EvalFeatures, EvalWeights, Game, SearchLimits, Winner};
+
+const USAGE: &str = "usage:
+  city-tune [--games N] [--size WxH] [--depth D] [--openings PLIES]
+            [--weights FILE] [--steps N] [--seed N] [--out FILE]";
+
+const FEATURES: usize = 5;
+const LEARNING_RATE: f64 = 0.2;
+
+struct Options {
+    games: usize,
+    size: (i32, i32),
+    depth: i32,
+    opening_plies: usize, // random moves before the engine takes over
+    weights: EvalWeights,
+    steps: usize, // gradient descent steps
+    seed: Option<u64>,
+    out: String,
+}
+
+// one position of a self-play game
+struct Sample {
+    features: [f64; FEATURES],
+    result: f64, // 1 blue won, 0.5 draw, 0 green won
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        games: 200,
+        size: (7, 7),
+        depth: 2,
+        opening_plies: 4,
+        weights: EvalWeights::default(),
+        steps: 2000,
+        seed: None,
+        out: "weights.json".to_string(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("bad value '{}' for {}", value, arg))
+        };
+        match arg.as_str() {
+            "--games" => options.games = number()? as usize,
+            "--size" => {
+                options.size =
+                    parse_board_size(value).ok_or(format!("bad board size '{}'", value))?;
+            }
+            "--depth" => options.depth = number()?.max(1) as i32,
+            "--openings" => options.opening_plies = number()? as usize,
+            "--weights" => options.weights = EvalWeights::load(value).map_err(|e| e.to_string())?,
+            "--steps" => options.steps = number()? as usize,
+            "--seed" => options.seed = Some(number()?),
+            "--out" => options.out = value.clone(),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(options)
+}
+
+fn feature_vector(features: &EvalFeatures) -> [f64; FEATURES] {
+    [
+        features.territory as f64,
+        features.mobility as f64,
+        features.pawn_distance as f64,
+        features.frontier as f64,
+        features.walls as f64,
+    ]
+}
+
+fn weight_vector(weights: &EvalWeights) -> [f64; FEATURES] {
+    [
+        weights.territory,
+        weights.mobility,
+        weights.pawn_distance,
+        weights.frontier,
+        weights.walls,
+    ]
+}
+
+fn weights_from_vector(vector: [f64; FEATURES]) -> EvalWeights {
+    EvalWeights {
+        territory: vector[0],
+        mobility: vector[1],
+        pawn_distance: vector[2],
+        frontier: vector[3],
+        walls: vector[4],
+    }
+}
+
+fn self_play(options: &Options, rng: &mut StdRng) -> Vec<Sample> {
+    let limits = SearchLimits {
+        move_time_ms: None,
+        max_depth: Some(options.depth),
+        ..SearchLimits::default()
+    };
+    let mut samples = Vec::new();
+    let mut wins = [0; 3]; // blue, green, draw
+
+    for index in 0..options.games {
+        let mut game = Game::new(options.size.0, options.size.1);
+        game.eval_weights = options.weights.clone();
+        game.seed = Some(rng.random());
+        for _ in 0..options.opening_plies {
+            if game.game_over() {
+                break;
+            }
+            let moves = game.possible_moves();
+            let mv = moves[rng.random_range(0..moves.len())];
+            game.make_move(mv, true, true)
+                .expect("listed moves are legal");
+        }
+
+        let mut positions = Vec::new();
+        while !game.game_over() {
+            positions.push(feature_vector(&game.evaluation_features()));
+            let best = game.iterative_deepening_minimax(&limits).best_move;
+            game.make_move(best, true, true)
+                .expect("the engine plays legal moves");
+        }
+
+        let (winner, _) = game.game_result();
+        let result = match winner {
+            Winner::Blue => 1.0,
+            Winner::Green => 0.0,
+            Winner::Draw => 0.5,
+        };
+        wins[winner as usize] += 1;
+        samples.extend(
+            positions
+                .into_iter()
+                .map(|features| Sample { features, result }),
+        );
+        eprint!(
+            "\rgame {}/{}: blue {} green {} draw {}, {} positions",
+            index + 1,
+            options.games,
+            wins[0],
+            wins[1],
+            wins[2],
+            samples.len()
+        );
+    }
+    eprintln!();
+    samples
+}
+
+fn predict(weights: &[f64; FEATURES], scale: f64, features: &[f64; FEATURES]) -> f64 {
+    let eval: f64 = weights.iter().zip(features).map(|(w, f)| w * f).sum();
+    1.0 / (1.0 + (-scale * eval).exp())
+}
+
+fn loss(samples: &[Sample], weights: &[f64; FEATURES], scale: f64) -> f64 {
+    let total: f64 = samples
+        .iter()
+        .map(|s| (s.result - predict(weights, scale, &s.features)).powi(2))
+        .sum();
+    total / samples.len() as f64
+}
+
+// the logistic scale that fits the starting weights best, by golden section
+// search over its logarithm
+fn fit_scale(samples: &[Sample], weights: &[f64; FEATURES]) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (0.001f64.ln(), 10f64.ln());
+    let cost = |log_scale: f64| loss(samples, weights, log_scale.exp());
+    for _ in 0..60 {
+        let a = high - ratio * (high - low);
+        let b = low + ratio * (high - low);
+        if cost(a) < cost(b) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+    ((low + high) / 2.0).exp()
+}
+
+// gradient descent on the mean squared error, each weight's step divided by
+// the mean square of its feature so features of different size learn alike
+fn fit_weights(
+    samples: &[Sample],
+    mut weights: [f64; FEATURES],
+    scale: f64,
+    steps: usize,
+) -> [f64; FEATURES] {
+    let n = samples.len() as f64;
+    let mut step_size = [0.0; FEATURES];
+    for (j, size) in step_size.iter_mut().enumerate() {
+        let mean_square = samples.iter().map(|s| s.features[j].powi(2)).sum::<f64>() / n;
+        *size = if mean_square > 0.0 {
+            LEARNING_RATE / (mean_square * scale * scale)
+        } else {
+            0.0 // the feature never changed, leave its weight alone
+        };
+    }
+
+    for _ in 0..steps {
+        let mut gradient = [0.0; FEATURES];
+        for sample in samples {
+            let p = predict(&weights, scale, &sample.features);
+            let error = (p - sample.result) * p * (1.0 - p) * scale;
+            for (g, f) in gradient.iter_mut().zip(&sample.features) {
+                *g += error * f;
+            }
+        }
+        for j in 0..FEATURES {
+            weights[j] -= step_size[j] * gradient[j] / n;
+        }
+    }
+    weights
+}
+
+// the engine reads its scores in cells of territory and clamps them short of
+// the scores of finished games, the fitted weights are scaled to match
+fn in_cells(samples: &[Sample], fitted: [f64; FEATURES]) -> Result<[f64; FEATURES], String> {
+    let territory = fitted[0];
+    if territory <= 0.0 {
+        return Err(format!(
+            "territory got no positive weight ({:.5}), play more games",
+            territory
+        ));
+    }
+    let weights = fitted.map(|weight| weight / territory);
+    let largest = samples
+        .iter()
+        .map(|s| {
+            weights
+                .iter()
+                .zip(&s.features)
+                .map(|(w, f)| w * f)
+                .sum::<f64>()
+        })
+        .fold(0.0, |largest: f64, eval| largest.max(eval.abs()));
+    println!("largest evaluation {:.1} cells", largest);
+    if largest >= (WIN_SCORE - 1) as f64 {
+        return Err("the fitted evaluation reaches the scores of finished games".to_string());
+    }
+    Ok(weights)
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    let samples = self_play(&options, &mut rng);
+    if samples.is_empty() {
+        return Err("the games ended before any position was recorded".to_string());
+    }
+
+    let start = weight_vector(&options.weights);
+    let scale = fit_scale(&samples, &start);
+    println!(
+        "{} positions, scale {:.4}, error {:.5}",
+        samples.len(),
+        scale,
+        loss(&samples, &start, scale)
+    );
+    let fitted = fit_weights(&samples, start, scale, options.steps);
+    println!("fitted error {:.5}", loss(&samples, &fitted, scale));
+
+    let weights = weights_from_vector(in_cells(&samples, fitted)?).to_json();
+    println!("{}", weights);
+    std::fs::write(&options.out, weights + "\n").map_err(|e| format!("{}: {}", options.out, e))?;
+    println!("written to {}", options.out);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = parse_options(&args).and_then(run) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,367 @@
+// Terminal front end for the city engine.
+//
+//     city play [--size 7x7] [--green] [--time MS] [--depth D]
+//     city analyse "<position string>" [--time MS] [--depth D] [--top N]
+//     city replay [--size 7x7 | --position "<position string>"] [MOVE ...]
+//
+// `replay` reads the moves from stdin when none are given on the command line.
+// `play` and `analyse` take `--engine mcts` with `--playout random|greedy|evaluation`
+// and `--iterations N` to search with Monte Carlo tree search instead of minimax.
+// `play --level beginner|easy|medium|hard|expert` plays a weaker, less predictable bot.
+// `--seed N` repeats the engine's random choices, searches print the seed they used.
+// `--weights FILE` loads evaluation weights from JSON, e.g. `{"territory": 1.0, "mobility": 0.2}`.
+
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use city_core::position::parse_board_size;
+use city_core::{
+    Coordinate, Difficulty, EvalWeights, Game, GameError, MctsConfig, Move, PlayoutPolicy,
+    SearchLimits, SearchResult, Winner,
+};
+
+const USAGE: &str = "usage:
+  city play [--size WxH] [--green] [--time MS] [--depth D] [--level L] [--seed N] [ENGINE]
+  city analyse <position> [--time MS] [--depth D] [--top N] [--seed N] [ENGINE]
+  city replay [--size WxH | --position <position>] [MOVE ...]
+ENGINE: [--weights FILE] [--engine minimax|mcts] [--playout random|greedy|evaluation] [--iterations N]";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Engine {
+    Minimax,
+    Mcts,
+}
+
+struct Options {
+    positional: Vec<String>,
+    size: (i32, i32),
+    position: Option<String>,
+    human_blue: bool,
+    limits: SearchLimits,
+    top: usize,
+    engine: Engine,
+    mcts: MctsConfig,
+    level: Option<Difficulty>,
+    seed: Option<u64>,
+    weights: EvalWeights,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        positional: Vec::new(),
+        size: (7, 7),
+        position: None,
+        human_blue: true,
+        limits: SearchLimits::default(),
+        top: 5,
+        engine: Engine::Minimax,
+        mcts: MctsConfig::default(),
+        level: None,
+        seed: None,
+        weights: EvalWeights::default(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", name))
+        };
+        match arg.as_str() {
+            "--size" => {
+                let size = value("--size")?;
+                options.size =
+                    parse_board_size(&size).ok_or(format!("bad board size '{}'", size))?;
+            }
+            "--position" => options.position = Some(value("--position")?),
+            "--green" => options.human_blue = false,
+            "--time" => {
+                let time = value("--time")?;
+                options.limits.move_time_ms =
+                    Some(time.parse().map_err(|_| format!("bad time '{}'", time))?);
+            }
+            "--depth" => {
+                let depth = value("--depth")?;
+                options.limits.max_depth = Some(
+                    depth
+                        .parse()
+                        .map_err(|_| format!("bad depth '{}'", depth))?,
+                );
+            }
+            "--top" => {
+                let top = value("--top")?;
+                options.top = top.parse().map_err(|_| format!("bad count '{}'", top))?;
+            }
+            "--engine" => {
+                options.engine = match value("--engine")?.as_str() {
+                    "minimax" => Engine::Minimax,
+                    "mcts" => Engine::Mcts,
+                    other => return Err(format!("unknown engine '{}'", other)),
+                };
+            }
+            "--playout" => {
+                options.mcts.playout = match value("--playout")?.as_str() {
+                    "random" => PlayoutPolicy::Random,
+                    "greedy" => PlayoutPolicy::Greedy,
+                    "evaluation" => PlayoutPolicy::Evaluation,
+                    other => return Err(format!("unknown playout '{}'", other)),
+                };
+            }
+            "--level" => {
+                options.level = Some(match value("--level")?.as_str() {
+                    "beginner" => Difficulty::Beginner,
+                    "easy" => Difficulty::Easy,
+                    "medium" => Difficulty::Medium,
+                    "hard" => Difficulty::Hard,
+                    "expert" => Difficulty::Expert,
+                    other => return Err(format!("unknown level '{}'", other)),
+                });
+            }
+            "--seed" => {
+                let seed = value("--seed")?;
+                options.seed = Some(seed.parse().map_err(|_| format!("bad seed '{}'", seed))?);
+            }
+            "--weights" => {
+                options.weights =
+                    EvalWeights::load(value("--weights")?).map_err(|e| e.to_string())?;
+            }
+            "--iterations" => {
+                let iterations = value("--iterations")?;
+                options.limits.max_nodes = Some(
+                    iterations
+                        .parse()
+                        .map_err(|_| format!("bad iterations '{}'", iterations))?,
+                );
+            }
+            _ => options.positional.push(arg.clone()),
+        }
+    }
+    Ok(options)
+}
+
+fn board_diagram(game: &Game) -> String {
+    // pawns are B and G, walls are drawn as | and ---
+    let position = game.to_position();
+    let mut out = String::from("   ");
+    for x in 0..game.width {
+        out += &format!(" {:^3}", city_core::notation::column_name(x));
+    }
+    out += "\n   +";
+    out += &"---+".repeat(game.width as usize);
+    out += "\n";
+
+    for y in 0..game.height {
+        out += &format!("{:>2} |", y + 1);
+        for x in 0..game.width {
+            let pos = Coordinate::new(x, y);
+            let pawn = if pos == position.blue_position {
+                'B'
+            } else if pos == position.green_position {
+                'G'
+            } else {
+                '.'
+            };
+            out += &format!(" {} ", pawn);
+            let wall = x == game.width - 1 || !position.vertical_walls.get(pos).is_empty();
+            out.push(if wall { '|' } else { ' ' });
+        }
+        out += "\n   +";
+        for x in 0..game.width {
+            let pos = Coordinate::new(x, y);
+            let wall = y == game.height - 1 || !position.horizontal_walls.get(pos).is_empty();
+            out += if wall { "---+" } else { "   +" };
+        }
+        out += "\n";
+    }
+    out
+}
+
+fn side_name(blue: bool) -> &'static str {
+    if blue { "blue" } else { "green" }
+}
+
+fn print_result(game: &mut Game) {
+    let (winner, score) = game.game_result();
+    let verdict = match winner {
+        Winner::Blue => "blue wins",
+        Winner::Green => "green wins",
+        Winner::Draw => "draw",
+    };
+    println!("game over: {} ({}-{})", verdict, score.blue, score.green);
+}
+
+fn search(game: &mut Game, options: &Options, limits: &SearchLimits) -> SearchResult {
+    match options.engine {
+        Engine::Minimax => game.iterative_deepening_minimax(limits),
+        Engine::Mcts => game
+            .mcts(limits, &options.mcts)
+            .expect("only running games are searched"),
+    }
+}
+
+fn play(options: Options) -> Result<(), String> {
+    let (width, height) = options.size;
+    let mut game = Game::new(width, height);
+    game.seed = options.seed;
+    game.eval_weights = options.weights.clone();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    println!(
+        "you play {}, enter moves like c3R, 'undo', 'moves' or 'quit'",
+        side_name(options.human_blue)
+    );
+    while !game.game_over() {
+        print!("{}", board_diagram(&game));
+        if game.blue_turn != options.human_blue
+            && let Some(level) = options.level
+        {
+            let mv = game.bot_move(level);
+            println!("{} plays {:?}", side_name(game.blue_turn), mv);
+            game.make_move(mv, true, true).map_err(|e| e.to_string())?;
+            continue;
+        }
+        if game.blue_turn != options.human_blue {
+            let result = search(&mut game, &options, &options.limits);
+            println!(
+                "{} plays {:?} (score {}, depth {}, {} nodes, seed {})",
+                side_name(game.blue_turn),
+                result.best_move,
+                result.score,
+                result.depth,
+                result.nodes,
+                result.seed
+            );
+            if let Some(proven) = result.proven {
+                println!("{}", proven);
+            }
+            game.make_move(result.best_move, true, true)
+                .map_err(|e| e.to_string())?;
+            continue;
+        }
+
+        print!("{} to move> ", side_name(game.blue_turn));
+        io::stdout().flush().map_err(|e| e.to_string())?;
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+        let line = line.map_err(|e| e.to_string())?;
+        match line.trim() {
+            "" => {}
+            "quit" | "exit" => return Ok(()),
+            "moves" => {
+                let moves: Vec<String> = game
+                    .possible_moves()
+                    .iter()
+                    .map(|mv| mv.to_notation())
+                    .collect();
+                println!("{}", moves.join(" "));
+            }
+            "undo" => {
+                // take back the engine reply and the own move
+                for _ in 0..2 {
+                    if let Err(err) = game.undo_move() {
+                        println!("{}", err);
+                        break;
+                    }
+                }
+            }
+            notation => {
+                let result = Move::from_notation_on_board(notation, width, height)
+                    .and_then(|mv| game.make_move(mv, true, true));
+                if let Err(err) = result {
+                    println!("{}", err);
+                }
+            }
+        }
+    }
+    print!("{}", board_diagram(&game));
+    print_result(&mut game);
+    Ok(())
+}
+
+fn analyse(options: Options) -> Result<(), String> {
+    let position = options
+        .positional
+        .first()
+        .ok_or("analyse needs a position string")?;
+    let mut game = Game::from_position_string(position).map_err(|e| e.to_string())?;
+    game.seed = options.seed;
+    game.eval_weights = options.weights.clone();
+    print!("{}", board_diagram(&game));
+    if game.game_over() {
+        print_result(&mut game);
+        return Ok(());
+    }
+
+    let limits = SearchLimits {
+        multi_pv: options.top,
+        ..options.limits.clone()
+    };
+    let result = search(&mut game, &options, &limits);
+    println!(
+        "best move {:?} score {} depth {} nodes {} seed {}",
+        result.best_move, result.score, result.depth, result.nodes, result.seed
+    );
+    if let Some(proven) = result.proven {
+        println!("proven: {}", proven);
+    }
+    println!("top moves:");
+    for line in &result.lines {
+        println!("  {:?}", line);
+    }
+    Ok(())
+}
+
+fn replay(options: Options) -> Result<(), String> {
+    let mut game = match &options.position {
+        Some(position) => Game::from_position_string(position).map_err(|e| e.to_string())?,
+        None => Game::new(options.size.0, options.size.1),
+    };
+
+    let mut moves = options.positional.clone();
+    if moves.is_empty() {
+        for line in io::stdin().lock().lines() {
+            let line = line.map_err(|e| e.to_string())?;
+            moves.extend(line.split_whitespace().map(str::to_string));
+        }
+    }
+
+    for (i, notation) in moves.iter().enumerate() {
+        let result: Result<(), GameError> =
+            Move::from_notation_on_board(notation, game.width, game.height)
+                .and_then(|mv| game.make_move(mv, true, true));
+        if let Err(err) = result {
+            return Err(format!("move {} ({}): {}", i + 1, notation, err));
+        }
+        println!("{}. {} {}", i + 1, side_name(!game.blue_turn), notation);
+        print!("{}", board_diagram(&game));
+        if game.game_over() {
+            break;
+        }
+    }
+    println!("{}", game.to_position_string());
+    if game.game_over() {
+        print_result(&mut game);
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(command) = args.first() else {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    };
+
+    let result = parse_options(&args[1..]).and_then(|options| match command.as_str() {
+        "play" => play(options),
+        "analyse" | "analyze" => analyse(options),
+        "replay" => replay(options),
+        _ => Err(USAGE.to_string()),
+    });
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
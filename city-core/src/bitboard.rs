@@ -0,0 +1,209 @@
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+use crate::game::{Cell, Coordinate, Direction, Move};
+use crate::position::Position;
+
+/// Largest width and height handled by the bitboard backend.
+pub const MAX_BITBOARD_SIZE: i32 = 16;
+
+const ROWS: usize = MAX_BITBOARD_SIZE as usize;
+
+/// A set of cells, one `u16` per row with bit `x` for column `x`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Bits([u16; ROWS]);
+
+impl Bits {
+    pub const EMPTY: Bits = Bits([0; ROWS]);
+
+    pub fn single(cell: Coordinate) -> Bits {
+        let mut bits = Bits::EMPTY;
+        bits.0[cell.y as usize] = 1 << cell.x;
+        bits
+    }
+
+    pub fn get(&self, cell: Coordinate) -> bool {
+        self.0[cell.y as usize] & (1 << cell.x) != 0
+    }
+
+    pub fn count(&self) -> i32 {
+        self.0.iter().map(|row| row.count_ones() as i32).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&row| row == 0)
+    }
+
+    pub fn without(self, other: Bits) -> Bits {
+        Bits(std::array::from_fn(|y| self.0[y] & !other.0[y]))
+    }
+
+    /// The cells in row-major order, the order `possible_moves` lists them in.
+    pub fn cells(self) -> impl Iterator<Item = Coordinate> {
+        (0..ROWS).flat_map(move |y| {
+            let mut row = self.0[y];
+            std::iter::from_fn(move || {
+                if row == 0 {
+                    return None;
+                }
+                let x = row.trailing_zeros();
+                row &= row - 1;
+                Some(Coordinate::new(x as i32, y as i32))
+            })
+        })
+    }
+}
+
+impl BitAnd for Bits {
+    type Output = Bits;
+
+    fn bitand(self, other: Bits) -> Bits {
+        Bits(std::array::from_fn(|y| self.0[y] & other.0[y]))
+    }
+}
+
+impl BitOr for Bits {
+    type Output = Bits;
+
+    fn bitor(self, other: Bits) -> Bits {
+        Bits(std::array::from_fn(|y| self.0[y] | other.0[y]))
+    }
+}
+
+impl BitOrAssign for Bits {
+    fn bitor_assign(&mut self, other: Bits) {
+        *self = *self | other;
+    }
+}
+
+/// The open edges of a board as masks, so a flood fill advances a whole
+/// frontier with a few shifts per row instead of visiting cells one by one.
+///
+/// Edges on the border are never open, which keeps every fill on the board.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WallBits {
+    open_right: Bits, // (x, y) can step to (x + 1, y)
+    open_down: Bits,  // (x, y) can step to (x, y + 1)
+}
+
+impl WallBits {
+    /// The masks for `position`, or `None` when the board is too large for them.
+    pub fn new(position: &Position) -> Option<WallBits> {
+        let (width, height) = (position.width, position.height);
+        if width > MAX_BITBOARD_SIZE || height > MAX_BITBOARD_SIZE {
+            return None;
+        }
+        let mut walls = WallBits {
+            open_right: Bits::EMPTY,
+            open_down: Bits::EMPTY,
+        };
+        for y in 0..height {
+            for x in 0..width {
+                let pos = Coordinate::new(x, y);
+                if x + 1 < width && position.vertical_walls.get(pos).is_empty() {
+                    walls.open_right.0[y as usize] |= 1 << x;
+                }
+                if y + 1 < height && position.horizontal_walls.get(pos).is_empty() {
+                    walls.open_down.0[y as usize] |= 1 << x;
+                }
+            }
+        }
+        Some(walls)
+    }
+
+    /// Close (or reopen) the edge `mv` puts its wall on.
+    pub fn set_wall(&mut self, mv: Move, wall: Cell) {
+        let (mask, pos) = match mv.place_wall {
+            Direction::Up => (&mut self.open_down, mv.destination.move_to(Direction::Up)),
+            Direction::Down => (&mut self.open_down, mv.destination),
+            Direction::Left => (
+                &mut self.open_right,
+                mv.destination.move_to(Direction::Left),
+            ),
+            Direction::Right => (&mut self.open_right, mv.destination),
+        };
+        let row = &mut mask.0[pos.y as usize];
+        if wall.is_empty() {
+            *row |= 1 << pos.x;
+        } else {
+            *row &= !(1 << pos.x);
+        }
+    }
+
+    // every cell one step away from a cell of `from`
+    fn step(&self, from: Bits) -> Bits {
+        let (right, down) = (&self.open_right.0, &self.open_down.0);
+        let mut next = Bits::EMPTY;
+        for y in 0..ROWS {
+            let row = from.0[y];
+            next.0[y] |= ((row & right[y]) << 1) | ((row >> 1) & right[y]);
+            if y + 1 < ROWS {
+                next.0[y + 1] |= row & down[y];
+            }
+            if y > 0 {
+                next.0[y - 1] |= row & down[y - 1];
+            }
+        }
+        next
+    }
+
+    /// Cells within `max_steps` of `start`, never entering a cell of `blocked`.
+    pub fn reachable(&self, start: Coordinate, blocked: Bits, max_steps: i32) -> Bits {
+        let mut reached = Bits::single(start);
+        let mut frontier = reached;
+        for _ in 0..max_steps {
+            frontier = self.step(frontier).without(reached | blocked);
+            if frontier.is_empty() {
+                break;
+            }
+            reached |= frontier;
+        }
+        reached
+    }
+
+    /// Cells blue reaches in fewer steps than green minus the other way round,
+    /// and the cells both reach in the same number of steps, neither pawn
+    /// walking through the other.
+    pub fn territory(&self, blue: Coordinate, green: Coordinate) -> (i32, i32) {
+        let (blue_start, green_start) = (Bits::single(blue), Bits::single(green));
+        let (mut blue_reached, mut green_reached) = (blue_start, green_start);
+        let (mut blue_frontier, mut green_frontier) = (blue_start, green_start);
+        let (mut blue_territory, mut green_territory) = (blue_start, green_start);
+        let mut contested = 0;
+
+        // grow both fills one step at a time, a cell first reached by both
+        // in the same step belongs to nobody
+        while !blue_frontier.is_empty() || !green_frontier.is_empty() {
+            blue_frontier = self.step(blue_frontier).without(blue_reached | green_start);
+            green_frontier = self
+                .step(green_frontier)
+                .without(green_reached | blue_start);
+            blue_territory |= blue_frontier.without(green_reached | green_frontier);
+            green_territory |= green_frontier.without(blue_reached | blue_frontier);
+            contested += (blue_frontier & green_frontier).count();
+            blue_reached |= blue_frontier;
+            green_reached |= green_frontier;
+        }
+        (blue_territory.count() - green_territory.count(), contested)
+    }
+
+    /// Every move to a cell of `destinations` with its wall on an open edge.
+    pub fn moves_from(&self, destinations: Bits) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for cell in destinations.cells() {
+            let (x, y) = (cell.x, cell.y as usize);
+            let open = |mask: &Bits, y: usize, x: i32| mask.0[y] & (1 << x) != 0;
+            let walls = [
+                (Direction::Up, y > 0 && open(&self.open_down, y - 1, x)),
+                (Direction::Down, open(&self.open_down, y, x)),
+                (Direction::Left, x > 0 && open(&self.open_right, y, x - 1)),
+                (Direction::Right, open(&self.open_right, y, x)),
+            ];
+            for (direction, open) in walls {
+                if open {
+                    moves.push(Move::new(cell, direction));
+                }
+            }
+        }
+        moves
+    }
+}
@@ -0,0 +1,141 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::game::{Game, Move};
+use crate::search::SearchLimits;
+
+// scores are clamped to this many cells before sampling, so a decided game
+// is only somewhat more certain than a clearly better position
+const SAMPLING_SCORE_CAP: f64 = 20.0;
+
+/// Playing strength of `Game::bot_move`, from an opponent new players can
+/// beat to the full search.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum Difficulty {
+    Beginner,
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+    Expert,
+}
+
+struct LevelSettings {
+    depth: i32,       // fixed search depth of every root move
+    noise: i32,       // largest random change to a root move's score, in cells
+    temperature: f64, // softmax temperature over the scores, lower plays closer to best
+}
+
+impl Difficulty {
+    // None for the full time limited search
+    fn settings(self) -> Option<LevelSettings> {
+        let (depth, noise, temperature) = match self {
+            Difficulty::Beginner => (1, 3, 3.0),
+            Difficulty::Easy => (2, 2, 1.5),
+            Difficulty::Medium => (3, 1, 0.75),
+            Difficulty::Hard => (4, 0, 0.25),
+            Difficulty::Expert => return None,
+        };
+        Some(LevelSettings {
+            depth,
+            noise,
+            temperature,
+        })
+    }
+}
+
+impl Game {
+    /// A move for the side to move at the given strength. Below `Expert` the
+    /// root moves are searched to a small fixed depth, their scores get some
+    /// noise and the move is drawn with softmax probabilities, so weaker
+    /// levels sometimes choose clearly worse moves. The choice repeats when
+    /// `Game::seed` is set. The game must not be over.
+    pub fn bot_move(&mut self, level: Difficulty) -> Move {
+        let Some(settings) = level.settings() else {
+            return self
+                .iterative_deepening_minimax(&SearchLimits::default())
+                .best_move;
+        };
+
+        let (_, mut rng) = self.search_rng();
+        let sign = if self.blue_turn { 1.0 } else { -1.0 };
+        let scored: Vec<(Move, f64)> = self
+            .minimax_evaluate_moves(settings.depth, &mut 0u64)
+            .into_iter()
+            .map(|em| {
+                let noise = rng.random_range(-settings.noise..=settings.noise);
+                let score =
+                    (sign * (em.ev + noise) as f64).clamp(-SAMPLING_SCORE_CAP, SAMPLING_SCORE_CAP);
+                (em.mv, score)
+            })
+            .collect();
+
+        let best = scored
+            .iter()
+            .map(|&(_, score)| score)
+            .fold(f64::NEG_INFINITY, f64::max);
+        // weights relative to the best move, so the exponent cannot overflow
+        let weights: Vec<f64> = scored
+            .iter()
+            .map(|&(_, score)| ((score - best) / settings.temperature).exp())
+            .collect();
+        let mut pick = rng.random_range(0.0..weights.iter().sum::<f64>());
+        for (&(mv, _), weight) in scored.iter().zip(&weights) {
+            if pick < *weight {
+                return mv;
+            }
+            pick -= weight;
+        }
+        scored.last().expect("a running game has moves").0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lower_levels_search_shallower_and_sample_wider() {
+        let levels = [
+            Difficulty::Beginner,
+            Difficulty::Easy,
+            Difficulty::Medium,
+            Difficulty::Hard,
+        ];
+        for pair in levels.windows(2) {
+            let (lower, higher) = (pair[0].settings().unwrap(), pair[1].settings().unwrap());
+            assert!(lower.depth < higher.depth);
+            assert!(lower.noise >= higher.noise);
+            assert!(lower.temperature > higher.temperature);
+        }
+        assert!(Difficulty::Expert.settings().is_none());
+    }
+
+    #[test]
+    fn hard_keeps_close_to_the_best_score_and_beginner_wanders() {
+        let mut game = Game::new(4, 4);
+        let scores = game.minimax_evaluate_moves(4, &mut 0u64);
+        let best = scores.iter().map(|em| em.ev).max().unwrap();
+        let mut beginner = Vec::new();
+        for seed in 0..5 {
+            game.seed = Some(seed);
+            let mv = game.bot_move(Difficulty::Hard);
+            let ev = scores.iter().find(|em| em.mv == mv).unwrap().ev;
+            assert!(ev >= best - 1, "{:?} scores {} against {}", mv, ev, best);
+            beginner.push(game.bot_move(Difficulty::Beginner));
+        }
+        beginner.sort_by_key(|mv| mv.to_flat());
+        beginner.dedup();
+        assert!(beginner.len() > 2, "{:?}", beginner);
+    }
+
+    #[test]
+    fn expert_plays_the_best_move_of_the_search() {
+        let mut game = Game::from_position_string("3x3 a1 c3 3/3 2/2/2 b").unwrap();
+        game.seed = Some(4);
+        let best = game
+            .iterative_deepening_minimax(&SearchLimits::default())
+            .best_move;
+        assert_eq!(game.bot_move(Difficulty::Expert), best);
+    }
+}
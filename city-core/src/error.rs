@@ -0,0 +1,93 @@
+use std::fmt;
+
+use crate::game::Coordinate;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameError {
+    IllegalDestination(Coordinate), // not reachable within three steps
+    WallAlreadyPresent,
+    WallOnBorder,
+    NotLatestPosition, // the board is showing an earlier move of the history
+    NothingToUndo,
+    MoveIndexOutOfRange(usize),
+    BadNotation(String),
+    BadPosition(String),
+    BadRecord(String),
+    BadWeights(String),
+}
+
+impl GameError {
+    /// Stable identifier handed to JavaScript as `error.code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GameError::IllegalDestination(_) => "ILLEGAL_DESTINATION",
+            GameError::WallAlreadyPresent => "WALL_ALREADY_PRESENT",
+            GameError::WallOnBorder => "WALL_ON_BORDER",
+            GameError::NotLatestPosition => "NOT_LATEST_POSITION",
+            GameError::NothingToUndo => "NOTHING_TO_UNDO",
+            GameError::MoveIndexOutOfRange(_) => "MOVE_INDEX_OUT_OF_RANGE",
+            GameError::BadNotation(_) => "BAD_NOTATION",
+            GameError::BadPosition(_) => "BAD_POSITION",
+            GameError::BadRecord(_) => "BAD_RECORD",
+            GameError::BadWeights(_) => "BAD_WEIGHTS",
+        }
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::IllegalDestination(c) => {
+                write!(f, "({}, {}) cannot be reached this turn", c.x, c.y)
+            }
+            GameError::WallAlreadyPresent => write!(f, "there is already a wall on this edge"),
+            GameError::WallOnBorder => write!(f, "walls cannot be placed on the border"),
+            GameError::NotLatestPosition => write!(f, "the board is not showing the latest move"),
+            GameError::NothingToUndo => write!(f, "there are no moves to undo"),
+            GameError::MoveIndexOutOfRange(index) => {
+                write!(f, "move index {} is past the end of the history", index)
+            }
+            GameError::BadNotation(notation) => write!(f, "invalid move notation '{}'", notation),
+            GameError::BadPosition(reason) => write!(f, "invalid position: {}", reason),
+            GameError::BadRecord(reason) => write!(f, "invalid game record: {}", reason),
+            GameError::BadWeights(reason) => write!(f, "invalid evaluation weights: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_match_the_typescript_union() {
+        let codes = [
+            (
+                GameError::IllegalDestination(Coordinate::new(0, 0)),
+                "ILLEGAL_DESTINATION",
+            ),
+            (GameError::WallAlreadyPresent, "WALL_ALREADY_PRESENT"),
+            (GameError::WallOnBorder, "WALL_ON_BORDER"),
+            (GameError::NotLatestPosition, "NOT_LATEST_POSITION"),
+            (GameError::NothingToUndo, "NOTHING_TO_UNDO"),
+            (GameError::MoveIndexOutOfRange(0), "MOVE_INDEX_OUT_OF_RANGE"),
+            (GameError::BadNotation(String::new()), "BAD_NOTATION"),
+            (GameError::BadPosition(String::new()), "BAD_POSITION"),
+            (GameError::BadRecord(String::new()), "BAD_RECORD"),
+            (GameError::BadWeights(String::new()), "BAD_WEIGHTS"),
+        ];
+        for (error, code) in &codes {
+            assert_eq!(error.code(), *code);
+        }
+
+        // the union in wasm.rs is written out by hand
+        let source = include_str!("wasm.rs");
+        let start = source.find("export type GameErrorCode =").unwrap();
+        let end = start + source[start..].find(';').unwrap();
+        let union: Vec<&str> = source[start..end].split('"').skip(1).step_by(2).collect();
+        let expected: Vec<&str> = codes.iter().map(|(_, code)| *code).collect();
+        assert_eq!(union, expected);
+    }
+}
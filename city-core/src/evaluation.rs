@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::GameError;
+use crate::game::{Cell, Game};
+
+/// The terms of the evaluation of a running game, from blue's point of view.
+///
+/// Pawn distance and frontier are the same for both pawns, they count for
+/// the side to move, which gets to the shared cells first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct EvalFeatures {
+    pub territory: i32,     // cells blue reaches first minus cells green reaches first
+    pub mobility: i32,      // blue's legal moves minus green's, as if each were to move
+    pub pawn_distance: i32, // row plus column distance of the pawns, negated with green to move
+    pub frontier: i32,      // cells both reach equally fast, negated with green to move
+    pub walls: i32,         // walls placed by blue minus walls placed by green
+}
+
+/// Weight of each feature, scores are in the unit of these weights. The
+/// default counts territory alone, one point per cell.
+///
+/// Read from JSON such as `{"territory": 1.0, "mobility": 0.1}`, missing
+/// weights are zero except territory.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EvalWeights {
+    pub territory: f64,
+    pub mobility: f64,
+    pub pawn_distance: f64,
+    pub frontier: f64,
+    pub walls: f64,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            territory: 1.0,
+            mobility: 0.0,
+            pawn_distance: 0.0,
+            frontier: 0.0,
+            walls: 0.0,
+        }
+    }
+}
+
+impl EvalWeights {
+    pub fn from_json(json: &str) -> Result<EvalWeights, GameError> {
+        serde_json::from_str(json).map_err(|e| GameError::BadWeights(e.to_string()))
+    }
+
+    /// Weights from a JSON file, see `from_json`.
+    pub fn load(path: impl AsRef<Path>) -> Result<EvalWeights, GameError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| GameError::BadWeights(format!("{}: {}", path.display(), e)))?;
+        EvalWeights::from_json(&json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("weights serialize")
+    }
+
+    pub fn score(&self, features: &EvalFeatures) -> f64 {
+        self.territory * features.territory as f64
+            + self.mobility * features.mobility as f64
+            + self.pawn_distance * features.pawn_distance as f64
+            + self.frontier * features.frontier as f64
+            + self.walls * features.walls as f64
+    }
+
+    // the plain territory count needs none of the other features
+    pub(crate) fn territory_only(&self) -> bool {
+        *self == EvalWeights::default()
+    }
+}
+
+impl Game {
+    /// The evaluation features of the current position, which should not be
+    /// a finished game.
+    pub fn evaluation_features(&mut self) -> EvalFeatures {
+        let (territory, contested) = self.territory();
+        let tempo = if self.blue_turn { 1 } else { -1 };
+
+        let own_moves = self.possible_moves().len() as i32;
+        self.blue_turn = !self.blue_turn;
+        let other_moves = self.possible_moves().len() as i32;
+        self.blue_turn = !self.blue_turn;
+        let mobility = (own_moves - other_moves) * tempo;
+
+        let distance = (self.blue_position.x - self.green_position.x).abs()
+            + (self.blue_position.y - self.green_position.y).abs();
+        let walls = self.horizontal_walls.count(Cell::Blue) + self.vertical_walls.count(Cell::Blue)
+            - self.horizontal_walls.count(Cell::Green)
+            - self.vertical_walls.count(Cell::Green);
+
+        EvalFeatures {
+            territory,
+            mobility,
+            pawn_distance: distance * tempo,
+            frontier: contested * tempo,
+            walls,
+        }
+    }
+}
@@ -2,15 +2,29 @@ use std::cmp::PartialEq;
 use std::collections::VecDeque;
 use std::fmt;
 use std::ops::Add;
-use std::time;
+use std::sync::Arc;
 
-use rand::Rng;
-extern crate wasm_bindgen;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-#[derive(Clone, Copy, Deserialize, Serialize)]
-#[wasm_bindgen]
+use crate::bitboard::{Bits, WallBits};
+use crate::error::GameError;
+use crate::evaluation::EvalWeights;
+use crate::ordering::{MoveOrdering, OrderingTables, hash_move_first};
+use crate::position::Position;
+use crate::search::{
+    LMR_MIN_DEPTH, LMR_MIN_MOVE_INDEX, LMR_REDUCTION, MAX_SEARCH_DEPTH, SearchControl,
+    SearchLimits, WIN_SCORE, terminal_score,
+};
+use crate::solver::{ProvenResult, SOLVER_MAX_AREA, SOLVER_MAX_NODES};
+use crate::transposition::{Bound, TranspositionTable, TtEntry};
+use crate::zobrist::ZobristKeys;
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[repr(u8)]
 pub enum Cell {
     Empty = 0,
@@ -20,10 +34,7 @@ pub enum Cell {
 
 impl Cell {
     pub fn is_empty(&self) -> bool {
-        match self {
-            Cell::Empty => true,
-            _ => false,
-        }
+        matches!(self, Cell::Empty)
     }
 }
 
@@ -35,7 +46,7 @@ impl fmt::Debug for Cell {
         }
     }
 }
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Board<T> {
     board_matrix: Vec<Vec<T>>,
 }
@@ -71,33 +82,27 @@ impl Board<bool> {
     }
 }
 
-impl Board<i32> {
-    fn clear(&mut self) {
-        for i in self.board_matrix.iter_mut() {
-            i.fill(-1);
-        }
-    }
-}
-
 impl Board<Cell> {
-    fn to_bool(&self) -> Vec<Vec<bool>> {
+    /// Number of walls placed by `owner`.
+    pub fn count(&self, owner: Cell) -> i32 {
         self.board_matrix
             .iter()
-            .map(|row| row.iter().map(|&cell| !cell.is_empty()).collect())
-            .collect()
+            .flatten()
+            .filter(|&&cell| cell == owner)
+            .count() as i32
     }
+}
 
+impl Board<i32> {
     fn clear(&mut self) {
-        for row in self.board_matrix.iter_mut() {
-            for cell in row.iter_mut() {
-                *cell = Cell::Empty;
-            }
+        for i in self.board_matrix.iter_mut() {
+            i.fill(-1);
         }
     }
 }
 
 #[derive(Copy, Clone, PartialEq, Serialize)]
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[repr(u8)]
 pub enum Direction {
     Up = 0,
@@ -137,7 +142,7 @@ impl fmt::Debug for Direction {
 }
 
 #[derive(Debug, Clone, Copy, Serialize)]
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct Coordinate {
     pub x: i32,
     pub y: i32,
@@ -149,7 +154,7 @@ impl Coordinate {
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Coordinate {
     pub fn new(x: i32, y: i32) -> Coordinate {
         Coordinate { x, y }
@@ -187,32 +192,14 @@ impl Add<Coordinate> for Coordinate {
 }
 
 #[derive(Clone, Copy, PartialEq, Serialize)]
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct Move {
     pub destination: Coordinate,
     pub place_wall: Direction,
 }
 
 impl Move {
-    pub fn from_notation(notation: &str) -> Result<Move, &'static str> {
-        if notation == "exit" {
-            panic!("exit")
-        }
-        let x = notation.chars().nth(0).ok_or("Invalid Notation")?;
-        let y = notation.chars().nth(1).ok_or("Invalid Notation")?;
-        let destination = Coordinate::new(x as i32 - 'a' as i32, y as i32 - '1' as i32);
-        let place_wall = match notation.chars().nth(2).ok_or("Invalid Notation")? {
-            'U' => Direction::Up,
-            'D' => Direction::Down,
-            'L' => Direction::Left,
-            'R' => Direction::Right,
-            _ => return Err("Invalid Notation"),
-        };
-
-        Ok(Move::new(destination, place_wall))
-    }
-
-    pub fn to_flat(&self) -> ((i32, i32), i32) {
+    pub fn to_flat(self) -> ((i32, i32), i32) {
         // destination, wall_direction UDLR -> 0123
         let wall_direction = match self.place_wall {
             Direction::Up => 0,
@@ -224,7 +211,7 @@ impl Move {
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Move {
     pub fn new(destination: Coordinate, place_wall: Direction) -> Move {
         Move {
@@ -236,34 +223,44 @@ impl Move {
 
 impl fmt::Debug for Move {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}{}{:?}",
-            (b'a' + self.destination.x as u8) as char,
-            (b'1' + self.destination.y as u8) as char,
-            self.place_wall
-        )
+        write!(f, "{}{:?}", self.destination.to_notation(), self.place_wall)
     }
 }
 
+#[derive(Clone, Serialize)]
 pub struct EvaluatedMove {
-    mv: Move,
-    ev: i32,
+    pub mv: Move,
+    pub ev: i32,
+    pub pv: Vec<Move>, // expected line starting with `mv`, empty when not searched
 }
 
 impl EvaluatedMove {
     pub fn new(mv: Move, ev: i32) -> EvaluatedMove {
-        EvaluatedMove { mv, ev }
+        EvaluatedMove {
+            mv,
+            ev,
+            pv: Vec::new(),
+        }
     }
 }
 
 impl fmt::Debug for EvaluatedMove {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let sign = if self.ev > 0 { "+" } else { "" };
-        write!(f, "{:?} ({}{})", self.mv, sign, self.ev)
+        write!(f, "{:?} ({}{})", self.mv, sign, self.ev)?;
+        if self.pv.len() > 1 {
+            write!(f, " {}", line_notation(&self.pv[1..]))?;
+        }
+        Ok(())
     }
 }
 
+/// Moves in notation separated by spaces.
+pub fn line_notation(line: &[Move]) -> String {
+    let moves: Vec<String> = line.iter().map(|mv| mv.to_notation()).collect();
+    moves.join(" ")
+}
+
 impl PartialEq for EvaluatedMove {
     // compare only by evaluation value
     fn eq(&self, other: &Self) -> bool {
@@ -275,7 +272,7 @@ impl Eq for EvaluatedMove {}
 
 impl PartialOrd for EvaluatedMove {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.ev.cmp(&other.ev))
+        Some(self.cmp(other))
     }
 }
 
@@ -285,10 +282,22 @@ impl Ord for EvaluatedMove {
     }
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchResult {
+    pub best_move: Move,
+    pub score: i32,
+    pub depth: i32,                   // deepest fully searched depth
+    pub nodes: u64,                   // nodes visited over all iterations
+    pub pv: Vec<Move>,                // principal variation, starts with `best_move`
+    pub lines: Vec<EvaluatedMove>,    // the best `multi_pv` moves with their own lines, best first
+    pub proven: Option<ProvenResult>, // set when the endgame solver searched to the end
+    pub seed: u64,                    // of the random choices, set as `Game::seed` to repeat them
+}
+
 #[derive(Debug, Serialize)]
 pub struct Score {
-    blue: i32,
-    green: i32,
+    pub blue: i32,
+    pub green: i32,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy, Serialize)]
@@ -308,34 +317,160 @@ pub struct Game {
 
     pub horizontal_walls: Board<Cell>, // 0 for no wall, 1 for blue wall, 2 for green wall
     pub vertical_walls: Board<Cell>,
+    bitboard: Option<WallBits>, // the walls again as masks, None on boards too large for them
 
     pub blue_turn: bool, // true for blue, false for green
 
     pub history: Vec<Move>,        // history of moves
     pub current_move_index: usize, // 1 ~ history.len()
+    start_position: Position,      // where history[0] was played from
+
+    pub parallel_search: bool, // split the root moves over rayon workers, ignored on wasm
+    pub move_ordering: MoveOrdering,
+    // null-window search for all but the first child, off by default: with
+    // the narrow aspiration windows it re-searches about as much as it saves
+    pub principal_variation_search: bool,
+    pub late_move_reductions: bool, // search late, low-ranked children shallower, not in parallel
+    pub endgame_solver: bool,       // solve positions with a small contested area exactly
+    pub seed: Option<u64>, // fixed seed for the engine's random choices, a new one per search if None
+    pub eval_weights: EvalWeights,
 
     blue_reachable_cache: Board<bool>,
     green_reachable_cache: Board<bool>,
     blue_steps_cache: Board<i32>,
     green_steps_cache: Board<i32>,
+
+    zobrist: Arc<ZobristKeys>,
+    pub(crate) hash: u64, // zobrist hash of the displayed position
+    // the table of earlier iterations and searches, shared by clones until
+    // one of them stores into it, and read only while the root moves of an
+    // iteration are searched
+    transposition_table: Arc<TranspositionTable>,
+    table_layer: TranspositionTable, // entries stored while one root move is searched
+    table_weights: EvalWeights,      // the evaluation the table's scores come from
+    ordering_tables: OrderingTables,
 }
 
 impl Game {
     pub fn new(width: i32, height: i32) -> Game {
-        Game {
+        Game::from_position(Position::initial(width, height))
+    }
+
+    pub fn from_position(position: Position) -> Game {
+        let (width, height) = (position.width, position.height);
+        let bitboard = WallBits::new(&position);
+        let mut game = Game {
             width,
             height,
-            blue_position: Coordinate::new(0, 0), // the top-left corner
-            green_position: Coordinate::new(width - 1, height - 1), // bottom-right corner
-            horizontal_walls: Board::new(width, height - 1, Cell::Empty),
-            vertical_walls: Board::new(width - 1, height, Cell::Empty),
-            blue_turn: true,
+            blue_position: position.blue_position,
+            green_position: position.green_position,
+            horizontal_walls: position.horizontal_walls.clone(),
+            vertical_walls: position.vertical_walls.clone(),
+            bitboard,
+            blue_turn: position.blue_turn,
             history: Vec::new(),
             current_move_index: 0,
+            start_position: position,
+            parallel_search: cfg!(not(target_arch = "wasm32")),
+            move_ordering: MoveOrdering::default(),
+            principal_variation_search: false,
+            late_move_reductions: true,
+            endgame_solver: true,
+            seed: None,
+            eval_weights: EvalWeights::default(),
             blue_reachable_cache: Board::new(width, height, false),
             green_reachable_cache: Board::new(width, height, false),
             blue_steps_cache: Board::new(width, height, -1),
             green_steps_cache: Board::new(width, height, -1),
+            zobrist: Arc::new(ZobristKeys::new(width, height)),
+            hash: 0,
+            transposition_table: Arc::new(TranspositionTable::default()),
+            table_layer: TranspositionTable::new(0),
+            table_weights: EvalWeights::default(),
+            ordering_tables: OrderingTables::new(width, height),
+        };
+        game.hash = game.compute_hash();
+        game
+    }
+
+    pub fn from_position_string(position: &str) -> Result<Game, GameError> {
+        Ok(Game::from_position(position.parse()?))
+    }
+
+    pub fn start_position(&self) -> &Position {
+        &self.start_position
+    }
+
+    /// The same game on the portable code paths, to check the bitboard against them.
+    #[cfg(test)]
+    pub(crate) fn without_bitboard(mut self) -> Game {
+        self.bitboard = None;
+        self
+    }
+
+    /// The seed for one search and a generator for all its random choices.
+    /// Seeds stay below 2^53 so they survive a round trip through JavaScript.
+    pub(crate) fn search_rng(&self) -> (u64, StdRng) {
+        let seed = self
+            .seed
+            .unwrap_or_else(|| rand::rng().random::<u64>() >> 11);
+        (seed, StdRng::seed_from_u64(seed))
+    }
+
+    pub fn to_position(&self) -> Position {
+        Position {
+            width: self.width,
+            height: self.height,
+            blue_position: self.blue_position,
+            green_position: self.green_position,
+            horizontal_walls: self.horizontal_walls.clone(),
+            vertical_walls: self.vertical_walls.clone(),
+            blue_turn: self.blue_turn,
+        }
+    }
+
+    pub fn to_position_string(&self) -> String {
+        // describes the displayed position
+        self.to_position().to_string()
+    }
+
+    pub(crate) fn compute_hash(&self) -> u64 {
+        // full recomputation, make_move/undo_move keep `hash` up to date incrementally
+        let mut hash = self.zobrist.blue_pawn(self.blue_position)
+            ^ self.zobrist.green_pawn(self.green_position);
+        for y in 0..self.height - 1 {
+            for x in 0..self.width {
+                let pos = Coordinate::new(x, y);
+                hash ^= self
+                    .zobrist
+                    .horizontal_wall(pos, *self.horizontal_walls.get(pos));
+            }
+        }
+        for y in 0..self.height {
+            for x in 0..self.width - 1 {
+                let pos = Coordinate::new(x, y);
+                hash ^= self
+                    .zobrist
+                    .vertical_wall(pos, *self.vertical_walls.get(pos));
+            }
+        }
+        if !self.blue_turn {
+            hash ^= self.zobrist.green_turn();
+        }
+        hash
+    }
+
+    fn wall_hash(&self, mv: Move, owner: Cell) -> u64 {
+        // key of the wall placed by `mv`
+        match mv.place_wall {
+            Direction::Up => self
+                .zobrist
+                .horizontal_wall(mv.destination.move_to(Direction::Up), owner),
+            Direction::Down => self.zobrist.horizontal_wall(mv.destination, owner),
+            Direction::Left => self
+                .zobrist
+                .vertical_wall(mv.destination.move_to(Direction::Left), owner),
+            Direction::Right => self.zobrist.vertical_wall(mv.destination, owner),
         }
     }
 
@@ -343,34 +478,46 @@ impl Game {
         self.current_move_index == self.history.len()
     }
 
-    pub fn set_current_move_index(&mut self, index: usize) {
+    // searches play and take back their moves at the end of the history, so
+    // a game showing an earlier move is searched as a copy cut back to it
+    pub(crate) fn cut_to_shown(&self) -> Game {
+        let mut game = self.clone();
+        game.history.truncate(game.current_move_index);
+        game
+    }
+
+    pub fn set_current_move_index(&mut self, index: usize) -> Result<(), GameError> {
         if index > self.history.len() {
-            panic!("Index out of bounds");
+            return Err(GameError::MoveIndexOutOfRange(index));
         }
 
         self.reset_board();
 
         for i in 0..index {
             let mv = self.history[i];
-            self.make_move(mv, false, false);
+            self.apply_move(mv);
         }
         self.current_move_index = index;
+        Ok(())
     }
 
     fn reset_board(&mut self) {
         // do not affect the history
         // reset the board to the initial state
 
-        self.blue_position = Coordinate::new(0, 0);
-        self.green_position = Coordinate::new(self.width - 1, self.height - 1);
-        self.horizontal_walls.clear();
-        self.vertical_walls.clear();
+        let start = &self.start_position;
+        self.blue_position = start.blue_position;
+        self.green_position = start.green_position;
+        self.horizontal_walls = start.horizontal_walls.clone();
+        self.vertical_walls = start.vertical_walls.clone();
+        self.bitboard = WallBits::new(start);
         self.current_move_index = 0;
-        self.blue_turn = true;
+        self.blue_turn = start.blue_turn;
         self.blue_reachable_cache.clear();
         self.green_reachable_cache.clear();
         self.blue_steps_cache.clear();
         self.green_steps_cache.clear();
+        self.hash = self.compute_hash();
     }
 
     fn reachable_with_cache(&mut self, start: Coordinate, step: i32, ignore_other_player: bool) {
@@ -474,7 +621,37 @@ impl Game {
         }
     }
 
+    fn reachable_in_one_move(&self, bits: &WallBits) -> Bits {
+        // where the side to move may stop, without passing the other pawn
+        let (start, other) = if self.blue_turn {
+            (self.blue_position, self.green_position)
+        } else {
+            (self.green_position, self.blue_position)
+        };
+        bits.reachable(start, Bits::single(other), 3)
+    }
+
+    pub(crate) fn reachable_areas(&mut self) -> (i32, i32) {
+        // number of cells each pawn reaches when walking through the other one
+        if let Some(bits) = &self.bitboard {
+            let blue = bits.reachable(self.blue_position, Bits::EMPTY, i32::MAX);
+            let green = bits.reachable(self.green_position, Bits::EMPTY, i32::MAX);
+            return (blue.count(), green.count());
+        }
+        let max_steps = self.width * self.height;
+        self.reachable_with_cache(self.blue_position, max_steps, true);
+        self.reachable_with_cache(self.green_position, max_steps, true);
+        (
+            self.blue_reachable_cache.total() as i32,
+            self.green_reachable_cache.total() as i32,
+        )
+    }
+
     pub fn possible_moves(&mut self) -> Vec<Move> {
+        if let Some(bits) = &self.bitboard {
+            return bits.moves_from(self.reachable_in_one_move(bits));
+        }
+
         let mut moves = Vec::new();
         let start = if self.blue_turn {
             self.blue_position
@@ -522,7 +699,16 @@ impl Game {
         moves
     }
 
-    fn evaluation_sorted_moves(&mut self, cutoff: i32) -> Vec<Move> {
+    pub(crate) fn evaluation_sorted_moves(&mut self, cutoff: i32) -> Vec<Move> {
+        let mut scored_moves = self.evaluation_scored_moves();
+        if cutoff > 0 && scored_moves.len() > cutoff as usize {
+            scored_moves.truncate(cutoff as usize);
+        }
+
+        scored_moves.into_iter().map(|em| em.mv).collect()
+    }
+
+    fn evaluation_scored_moves(&mut self) -> Vec<EvaluatedMove> {
         // evaluate all possible moves and return them sorted by evaluation value
         let mut scored_moves: Vec<EvaluatedMove> = self
             .possible_moves()
@@ -538,67 +724,153 @@ impl Game {
         if self.blue_turn {
             scored_moves.reverse(); // descending for max player
         }
+        scored_moves
+    }
 
-        if cutoff > 0 && scored_moves.len() > cutoff as usize {
-            scored_moves.truncate(cutoff as usize);
+    pub fn make_move(
+        &mut self,
+        mv: Move,
+        safe: bool,
+        add_to_history: bool,
+    ) -> Result<(), GameError> {
+        // make the move
+        // if safe, the move is checked against the rules first
+        if self.is_showing_latest() || !add_to_history {
+            if safe {
+                self.validate_move(mv)?;
+            }
+            self.apply_move(mv);
+        } else if safe {
+            // the displayed position is not the one the move would be played in
+            return Err(GameError::NotLatestPosition);
         }
-
-        scored_moves.into_iter().map(|em| em.mv).collect()
+        if add_to_history {
+            if self.is_showing_latest() {
+                self.current_move_index += 1;
+            }
+            self.history.push(mv);
+        }
+        Ok(())
     }
 
-    pub fn make_move(&mut self, mv: Move, safe: bool, add_to_history: bool) -> bool {
-        // make the move
-        // if mv is in possible_moves, then make the move and place
+    /// Add a move to the end of the history, checked against the latest
+    /// position, while the board keeps showing the same move.
+    pub fn append_move(&mut self, mv: Move) -> Result<(), GameError> {
+        if self.is_showing_latest() {
+            return self.make_move(mv, true, true);
+        }
+        let mut latest = self.clone();
+        latest.set_current_move_index(self.history.len())?;
+        latest.validate_move(mv)?;
+        self.history.push(mv);
+        Ok(())
+    }
 
-        // return true if the move is made, false otherwise
-        if self.is_showing_latest() && add_to_history || !add_to_history {
-            if safe && !self.possible_moves().contains(&mv) {
-                return false;
+    fn validate_move(&mut self, mv: Move) -> Result<(), GameError> {
+        // same rules as possible_moves, with the reason a move is rejected
+        if !mv.destination.inside(self.width, self.height) {
+            return Err(GameError::IllegalDestination(mv.destination));
+        }
+        let reachable = match &self.bitboard {
+            Some(bits) => self.reachable_in_one_move(bits).get(mv.destination),
+            None => {
+                let start = if self.blue_turn {
+                    self.blue_position
+                } else {
+                    self.green_position
+                };
+                self.reachable_with_cache(start, 3, false);
+                let reachable = if self.blue_turn {
+                    &self.blue_reachable_cache
+                } else {
+                    &self.green_reachable_cache
+                };
+                *reachable.get(mv.destination)
             }
+        };
+        if !reachable {
+            return Err(GameError::IllegalDestination(mv.destination));
+        }
 
-            if self.blue_turn {
-                self.blue_position = mv.destination;
-            } else {
-                self.green_position = mv.destination;
-            }
+        let next = mv.destination.move_to(mv.place_wall);
+        if !next.inside(self.width, self.height) {
+            return Err(GameError::WallOnBorder);
+        }
+        let wall = match mv.place_wall {
+            Direction::Up => self.horizontal_walls.get(next),
+            Direction::Down => self.horizontal_walls.get(mv.destination),
+            Direction::Left => self.vertical_walls.get(next),
+            Direction::Right => self.vertical_walls.get(mv.destination),
+        };
+        if !wall.is_empty() {
+            return Err(GameError::WallAlreadyPresent);
+        }
+        Ok(())
+    }
 
-            let cell = if self.blue_turn {
-                Cell::Blue
-            } else {
-                Cell::Green
-            };
+    pub(crate) fn push_move(&mut self, mv: Move) {
+        // play an already validated move on the latest position
+        self.apply_move(mv);
+        self.current_move_index += 1;
+        self.history.push(mv);
+    }
 
-            match mv.place_wall {
-                Direction::Up => self
-                    .horizontal_walls
-                    .set(mv.destination.move_to(Direction::Up), cell),
-                Direction::Down => self.horizontal_walls.set(mv.destination, cell),
-                Direction::Left => self
-                    .vertical_walls
-                    .set(mv.destination.move_to(Direction::Left), cell),
-                Direction::Right => self.vertical_walls.set(mv.destination, cell),
-            }
+    fn apply_move(&mut self, mv: Move) {
+        // update the board only, the history is left untouched
+        if self.blue_turn {
+            self.hash ^=
+                self.zobrist.blue_pawn(self.blue_position) ^ self.zobrist.blue_pawn(mv.destination);
+            self.blue_position = mv.destination;
+        } else {
+            self.hash ^= self.zobrist.green_pawn(self.green_position)
+                ^ self.zobrist.green_pawn(mv.destination);
+            self.green_position = mv.destination;
+        }
+
+        let cell = if self.blue_turn {
+            Cell::Blue
+        } else {
+            Cell::Green
+        };
+        self.hash ^= self.wall_hash(mv, cell) ^ self.zobrist.green_turn();
 
-            self.blue_turn = !self.blue_turn;
+        match mv.place_wall {
+            Direction::Up => self
+                .horizontal_walls
+                .set(mv.destination.move_to(Direction::Up), cell),
+            Direction::Down => self.horizontal_walls.set(mv.destination, cell),
+            Direction::Left => self
+                .vertical_walls
+                .set(mv.destination.move_to(Direction::Left), cell),
+            Direction::Right => self.vertical_walls.set(mv.destination, cell),
         }
-        if add_to_history {
-            if self.is_showing_latest() {
-                self.current_move_index += 1;
-            }
-            self.history.push(mv);
+        if let Some(bits) = &mut self.bitboard {
+            bits.set_wall(mv, cell);
         }
-        true
+
+        self.blue_turn = !self.blue_turn;
     }
 
-    pub fn undo_move(&mut self) {
-        let last_move = self.history.pop().expect("No moves to undo");
+    pub fn undo_move(&mut self) -> Result<Move, GameError> {
+        if self.history.is_empty() {
+            return Err(GameError::NothingToUndo);
+        }
+        if !self.is_showing_latest() {
+            return Err(GameError::NotLatestPosition);
+        }
+        Ok(self.pop_move())
+    }
+
+    pub(crate) fn pop_move(&mut self) -> Move {
+        // take back the last move of a non-empty history shown at its latest position
+        let last_move = self.history.pop().expect("pop_move on an empty history");
+        self.current_move_index -= 1;
 
+        // the player who made last_move is the one not to move now
         let last_position = match self.history.len() {
-            0 => Coordinate { x: 0, y: 0 }, // if no moves left, reset to start
-            1 => Coordinate {
-                x: self.width - 1,
-                y: self.height - 1,
-            }, // if only one move left, reset to green position
+            // their first move, go back to where they started
+            0 | 1 if self.blue_turn => self.start_position.green_position,
+            0 | 1 => self.start_position.blue_position,
             _ => {
                 let second_last_move = self.history[self.history.len() - 2];
                 second_last_move.destination
@@ -618,20 +890,40 @@ impl Game {
                 .set(last_move.destination.move_to(Direction::Left), Cell::Empty),
             Direction::Right => self.vertical_walls.set(last_move.destination, Cell::Empty),
         }
+        if let Some(bits) = &mut self.bitboard {
+            bits.set_wall(last_move, Cell::Empty);
+        }
 
         // reset the position
         if self.blue_turn {
+            self.hash ^= self.wall_hash(last_move, Cell::Green)
+                ^ self.zobrist.green_pawn(self.green_position)
+                ^ self.zobrist.green_pawn(last_position);
             self.green_position = last_position;
         } else {
+            self.hash ^= self.wall_hash(last_move, Cell::Blue)
+                ^ self.zobrist.blue_pawn(self.blue_position)
+                ^ self.zobrist.blue_pawn(last_position);
             self.blue_position = last_position;
         }
 
+        self.hash ^= self.zobrist.green_turn();
         self.blue_turn = !self.blue_turn;
+        last_move
+    }
+
+    /// Cells blue reaches in fewer steps than green minus the other way round,
+    /// the evaluation of a running game with the default weights.
+    pub fn territory_difference(&mut self) -> i32 {
+        self.territory().0
     }
 
-    fn territory_difference(&mut self) -> i32 {
+    pub(crate) fn territory(&mut self) -> (i32, i32) {
         // if it takes less steps for one player to reach a cell, the the cell is counted as the player's territory
-        // always return blue territory - green territory
+        // always return blue territory - green territory, and the cells both reach in the same number of steps
+        if let Some(bits) = &self.bitboard {
+            return bits.territory(self.blue_position, self.green_position);
+        }
         self.steps_with_cache(self.blue_position);
         self.steps_with_cache(self.green_position);
         let blue_dist = &mut self.blue_steps_cache;
@@ -639,6 +931,7 @@ impl Game {
 
         let mut blue_territory = 0;
         let mut green_territory = 0;
+        let mut contested = 0;
 
         for y in 0..self.height {
             for x in 0..self.width {
@@ -653,11 +946,26 @@ impl Game {
                 // if green reaches faster (or blue can't reach), count for green
                 else if gd >= 0 && (bd < 0 || gd < bd) {
                     green_territory += 1;
+                } else if bd >= 0 {
+                    contested += 1;
                 }
             }
         }
 
-        blue_territory - green_territory
+        (blue_territory - green_territory, contested)
+    }
+
+    fn game_over_score(&mut self) -> i32 {
+        // the final difference in cells, sooner is better for the winner
+        let (blue_score, green_score) = self.reachable_areas();
+        terminal_score(blue_score - green_score, self.current_move_index)
+    }
+
+    fn best_possible_score(&mut self) -> i32 {
+        // no side can finish better than taking all of the shared area but
+        // the other pawn's cell, with the very next move
+        let area = self.reachable_areas().0;
+        terminal_score(area - 2, self.current_move_index + 1)
     }
 
     fn evaluate(&mut self) -> i32 {
@@ -665,25 +973,21 @@ impl Game {
         // larger positive value means better for blue, larger negative value means better for green
 
         if self.game_over() {
-            self.reachable_with_cache(self.blue_position, self.height * self.height, true);
-            self.reachable_with_cache(self.green_position, self.height * self.height, true);
-            let blue_reachable = &mut self.blue_reachable_cache;
-            let green_reachable = &mut self.green_reachable_cache;
-
-            let blue_score = blue_reachable.total() as i32;
-            let green_score = green_reachable.total() as i32;
-
-            if blue_score > green_score {
-                return 100; // Blue wins
-            } else if green_score > blue_score {
-                return -100; // Green wins
-            } else {
-                return 0; // Draw
-            }
+            return self.game_over_score();
         }
+        self.static_evaluation()
+    }
 
-        let territory_diff = self.territory_difference();
-        territory_diff
+    /// The evaluation of a running game under `eval_weights`, as it scores the
+    /// leaves of the search.
+    pub(crate) fn static_evaluation(&mut self) -> i32 {
+        if self.eval_weights.territory_only() {
+            return self.territory_difference();
+        }
+        // weighted features stay clear of the scores of finished games
+        let features = self.evaluation_features();
+        let score = self.eval_weights.score(&features).round();
+        score.clamp(-(WIN_SCORE - 1) as f64, (WIN_SCORE - 1) as f64) as i32
     }
 
     fn minimax_evaluate(
@@ -691,71 +995,308 @@ impl Game {
         depth: i32,
         mut alpha: i32,
         mut beta: i32,
-        nodes: &mut u64,
+        control: &SearchControl,
         cutoff: i32,
+        pv: &mut Vec<Move>,
     ) -> i32 {
-        *nodes += 1;
+        // `pv` receives the line this node expects, starting with its best move
+        pv.clear();
+        if control.visit() {
+            return 0; // aborted, the caller throws this iteration away
+        }
 
         if self.game_over() {
-            self.reachable_with_cache(self.blue_position, self.height * self.height, true);
-            self.reachable_with_cache(self.green_position, self.height * self.height, true);
-            let blue_reachable = &mut self.blue_reachable_cache;
-            let green_reachable = &mut self.green_reachable_cache;
-
-            let blue_score = blue_reachable.total() as i32;
-            let green_score = green_reachable.total() as i32;
-
-            if blue_score > green_score {
-                return 100; // Blue wins
-            } else if green_score > blue_score {
-                return -100; // Green wins
-            } else {
-                return 0; // Draw
-            }
+            return self.game_over_score();
+        }
+
+        if depth <= 0 {
+            return self.static_evaluation();
         }
 
-        let moves = match depth {
-            0 => return self.territory_difference(),
-            1 => self.possible_moves(),
-            _ => self.evaluation_sorted_moves(cutoff),
+        // only reuse scores searched to exactly this depth, so the result
+        // does not depend on what happens to be in the table
+        let key = self.hash;
+        let entry = self
+            .table_layer
+            .probe(key)
+            .or_else(|| self.transposition_table.probe(key));
+        let hash_move = match entry.copied() {
+            Some(entry) => {
+                if entry.depth == depth {
+                    match entry.bound {
+                        Bound::Exact => {
+                            pv.extend(entry.best_move);
+                            return entry.score;
+                        }
+                        Bound::Lower if entry.score >= beta => return entry.score,
+                        Bound::Upper if entry.score <= alpha => return entry.score,
+                        _ => {}
+                    }
+                }
+                entry.best_move
+            }
+            None => None,
         };
+
+        let ply = self.history.len();
+        let mut moves = match self.move_ordering {
+            MoveOrdering::Static if depth == 1 => self.possible_moves(),
+            MoveOrdering::Static => self.evaluation_sorted_moves(cutoff),
+            MoveOrdering::Heuristic => {
+                let mut moves = self.possible_moves();
+                self.ordering_tables.sort(&mut moves, self.blue_turn, ply);
+                if cutoff > 0 {
+                    moves.truncate(cutoff as usize);
+                }
+                moves
+            }
+        };
+        hash_move_first(&mut moves, hash_move);
+
+        let (alpha_orig, beta_orig) = (alpha, beta);
         let mut value = if self.blue_turn { i32::MIN } else { i32::MAX };
-        for mv in moves {
-            self.make_move(mv, false, true);
-            let score = self.minimax_evaluate(depth - 1, alpha, beta, nodes, cutoff);
-            self.undo_move();
-            if self.blue_turn {
-                value = value.max(score);
-                alpha = alpha.max(value);
-                if alpha == 100 {
-                    return 100;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        for (index, mv) in moves.into_iter().enumerate() {
+            // a null window only tells whether the move beats the best one so far
+            let maximizing = self.blue_turn;
+            let (null_alpha, null_beta) = if maximizing {
+                (alpha, alpha.saturating_add(1))
+            } else {
+                (beta.saturating_sub(1), beta)
+            };
+            let beats_best = |score: i32| {
+                if maximizing {
+                    score > alpha
+                } else {
+                    score < beta
                 }
+            };
+
+            self.push_move(mv);
+            let mut score = None;
+            let mut reduced_beat_best = false;
+            if self.late_move_reductions && index >= LMR_MIN_MOVE_INDEX && depth >= LMR_MIN_DEPTH {
+                // a reduced search that beats the best move is verified at full
+                // depth, with a null window first
+                let reduced = depth - 1 - LMR_REDUCTION;
+                let sc = self.minimax_evaluate(
+                    reduced,
+                    null_alpha,
+                    null_beta,
+                    control,
+                    cutoff,
+                    &mut child_pv,
+                );
+                score = Some(sc).filter(|&sc| !beats_best(sc));
+                reduced_beat_best = score.is_none();
+            }
+            if score.is_none()
+                && index > 0
+                && (self.principal_variation_search || reduced_beat_best)
+            {
+                // full depth, the exact score is only needed inside the window
+                let sc = self.minimax_evaluate(
+                    depth - 1,
+                    null_alpha,
+                    null_beta,
+                    control,
+                    cutoff,
+                    &mut child_pv,
+                );
+                score = Some(sc).filter(|&sc| sc <= alpha || sc >= beta);
+            }
+            let score = match score {
+                Some(score) => score,
+                None => {
+                    self.minimax_evaluate(depth - 1, alpha, beta, control, cutoff, &mut child_pv)
+                }
+            };
+            self.pop_move();
+            if control.stopped() {
+                return 0; // aborted, the other children are not needed either
+            }
+            let improved = if self.blue_turn {
+                score > value
+            } else {
+                score < value
+            };
+            if improved || best_move.is_none() {
+                value = score;
+                best_move = Some(mv);
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+            }
+            // stop early once nothing can beat an already found win
+            let won = if self.blue_turn {
+                alpha = alpha.max(value);
+                alpha >= WIN_SCORE && alpha >= self.best_possible_score()
             } else {
-                value = value.min(score);
                 beta = beta.min(value);
-                if beta == -100 {
-                    return -100;
+                beta <= -WIN_SCORE && beta <= -self.best_possible_score()
+            };
+            if won || alpha >= beta {
+                if self.move_ordering == MoveOrdering::Heuristic {
+                    let blue_turn = self.blue_turn;
+                    self.ordering_tables
+                        .record_cutoff(mv, blue_turn, ply, depth);
                 }
-            }
-            if alpha >= beta {
                 break;
             }
         }
+
+        if control.stopped() {
+            return value;
+        }
+
+        let bound = if value <= alpha_orig {
+            Bound::Upper
+        } else if value >= beta_orig {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table_layer.store(TtEntry {
+            key,
+            depth,
+            score: value,
+            bound,
+            best_move,
+        });
         value
     }
 
-    pub fn minimax_evaluate_moves(&mut self, depth: i32, nodes: &mut u64) -> Vec<EvaluatedMove> {
-        // evaluate all first‐level moves and return them sorted
-        let mut scored: Vec<EvaluatedMove> = self
-            .evaluation_sorted_moves(0)
+    fn evaluate_root_moves(
+        &mut self,
+        moves: Vec<Move>,
+        depth: i32,
+        alpha: i32,
+        beta: i32,
+        control: &SearchControl,
+    ) -> Vec<EvaluatedMove> {
+        // score every first-level move with the same window, in input order
+        if self.table_weights != self.eval_weights {
+            // the stored scores were searched with other weights
+            let capacity = self.transposition_table.capacity();
+            self.transposition_table = Arc::new(TranspositionTable::new(capacity));
+            self.table_weights = self.eval_weights.clone();
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.parallel_search {
+            return self.evaluate_root_moves_in_parallel(moves, depth, alpha, beta, control);
+        }
+
+        // one move after the other, each one using what the moves before it
+        // stored and learned, with the whole table taken over as the layer
+        let table = Arc::make_mut(&mut self.transposition_table);
+        self.table_layer = std::mem::take(table);
+        let scored = moves
             .into_iter()
             .map(|mv| {
-                self.make_move(mv, false, true);
-                let sc = self.minimax_evaluate(depth - 1, i32::MIN, i32::MAX, nodes, 0);
-                self.undo_move();
-                EvaluatedMove::new(mv, sc)
+                if control.stopped() {
+                    // the iteration is thrown away
+                    return EvaluatedMove::new(mv, 0);
+                }
+                let mut pv = Vec::new();
+                self.push_move(mv);
+                let ev = self.minimax_evaluate(depth - 1, alpha, beta, control, 0, &mut pv);
+                self.pop_move();
+                pv.insert(0, mv);
+                EvaluatedMove { mv, ev, pv }
             })
             .collect();
+        let table = std::mem::replace(&mut self.table_layer, TranspositionTable::new(0));
+        self.transposition_table = Arc::new(table);
+        scored
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn evaluate_root_moves_in_parallel(
+        &mut self,
+        moves: Vec<Move>,
+        depth: i32,
+        alpha: i32,
+        beta: i32,
+        control: &SearchControl,
+    ) -> Vec<EvaluatedMove> {
+        use rayon::prelude::*;
+
+        // Each move is searched from the tables as they were before the
+        // first, so its score does not depend on how the moves are scheduled,
+        // and what the searches stored is merged back in the order of the
+        // moves afterwards. Late move reductions would make the scores depend
+        // on the tables the moves start from, without them the tables only
+        // change how fast a score is found, so the scores are the ones of the
+        // sequential search without reductions.
+
+        // together the layers are about as large as the table they go into
+        let layer_capacity = self.transposition_table.capacity() / moves.len().max(1);
+        let snapshot = self.ordering_tables.clone();
+        let search_move = |game: &mut Game, mv: Move| {
+            if control.stopped() {
+                // the iteration is thrown away, leave the other moves alone
+                return (
+                    EvaluatedMove::new(mv, 0),
+                    snapshot.clone(),
+                    TranspositionTable::new(0),
+                );
+            }
+            game.ordering_tables.clone_from(&snapshot);
+            game.table_layer = TranspositionTable::new(layer_capacity);
+            let mut pv = Vec::new();
+            game.push_move(mv);
+            let sc = game.minimax_evaluate(depth - 1, alpha, beta, control, 0, &mut pv);
+            game.pop_move();
+            pv.insert(0, mv);
+            let layer = std::mem::replace(&mut game.table_layer, TranspositionTable::new(0));
+            (
+                EvaluatedMove { mv, ev: sc, pv },
+                game.ordering_tables.clone(),
+                layer,
+            )
+        };
+
+        // every worker searches its own copy of the game
+        let mut base = self.clone();
+        base.late_move_reductions = false;
+        let base = &base;
+        let searched = moves
+            .par_iter()
+            .map_init(|| base.clone(), |game, &mv| search_move(game, mv))
+            .collect();
+        self.merge_root_searches(snapshot, searched)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn merge_root_searches(
+        &mut self,
+        snapshot: OrderingTables,
+        searched: Vec<(EvaluatedMove, OrderingTables, TranspositionTable)>,
+    ) -> Vec<EvaluatedMove> {
+        self.ordering_tables.clone_from(&snapshot);
+        let table = Arc::make_mut(&mut self.transposition_table);
+        searched
+            .into_iter()
+            .map(|(evaluated, tables, layer)| {
+                self.ordering_tables.merge(&tables, &snapshot);
+                table.merge(&layer);
+                evaluated
+            })
+            .collect()
+    }
+
+    fn sorted_root_moves(
+        &mut self,
+        depth: i32,
+        alpha: i32,
+        beta: i32,
+        control: &SearchControl,
+    ) -> Vec<EvaluatedMove> {
+        // evaluate all first‐level moves and return them sorted
+        let moves = self.evaluation_sorted_moves(0);
+        let mut scored = self.evaluate_root_moves(moves, depth, alpha, beta, control);
 
         scored.sort();
 
@@ -766,108 +1307,148 @@ impl Game {
         scored
     }
 
-    pub fn iterative_deepening_minimax(&mut self, depth: i32) -> EvaluatedMove {
+    pub fn minimax_evaluate_moves(&mut self, depth: i32, nodes: &mut u64) -> Vec<EvaluatedMove> {
+        if !self.is_showing_latest() {
+            return self.cut_to_shown().minimax_evaluate_moves(depth, nodes);
+        }
+        let control = SearchControl::unlimited();
+        let scored = self.sorted_root_moves(depth, i32::MIN, i32::MAX, &control);
+        *nodes += control.nodes();
+        scored
+    }
+
+    pub fn iterative_deepening_minimax(&mut self, limits: &SearchLimits) -> SearchResult {
+        let control = SearchControl::from_limits(limits);
+        self.iterative_deepening_search(limits, &control, |_| {})
+    }
+
+    /// Iterative deepening under an externally owned `control`, which another
+    /// thread may `stop()`. Only the depth and line count of `limits` are used
+    /// here, time and nodes are up to `control`. `on_iteration` sees the
+    /// result of every completed depth.
+    pub fn iterative_deepening_search(
+        &mut self,
+        limits: &SearchLimits,
+        control: &SearchControl,
+        mut on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        if !self.is_showing_latest() {
+            return self
+                .cut_to_shown()
+                .iterative_deepening_search(limits, control, on_iteration);
+        }
         // iterative deepening minimax with aspiration windows
-        let start = time::Instant::now();
-        let max_depth = match self.history.len().cmp(&6) {
-            std::cmp::Ordering::Less => depth + 2, // if less than 6 moves, use 6
-            _ => depth + 4,                        // otherwise, use history length + 2
-        }; // Maximum depth to search
-        let time_limit_secs = 3; // Time limit in seconds
-
-        // Initial search at the base depth to get a starting value
-        let evaluated_moves = self.minimax_evaluate_moves(depth, &mut 0u64);
-        let mut best_move = evaluated_moves[0].mv;
-        let mut best_score = evaluated_moves[0].ev;
-        let mut current_depth = depth + 2;
+        self.ordering_tables.new_search();
+        let (seed, mut rng) = self.search_rng();
+
+        let max_depth = limits.max_depth.unwrap_or(MAX_SEARCH_DEPTH).max(1);
+        // several lines need exact scores for every root move, so they are
+        // searched with a full window instead of an aspiration window
+        let multi_pv = limits.multi_pv.max(1);
+
+        // small endgames are solved to the end, the heuristic search is
+        // only needed when the solver runs out of its share of the budget
+        if self.endgame_solver
+            && self
+                .contested_area()
+                .is_some_and(|area| area <= SOLVER_MAX_AREA)
+            && let Some((lines, proven)) = self.solved_lines(control, multi_pv)
+        {
+            let mut result = search_result(lines, multi_pv, 0, control.nodes(), seed, &mut rng);
+            result.depth = result.pv.len() as i32;
+            result.proven = Some(proven);
+            on_iteration(&result);
+            return result;
+        }
+
+        // iterations step by two plies, start on the parity of max_depth
+        let mut current_depth = 2 - max_depth % 2;
+
+        // Initial search to get a starting value, when even that runs out
+        // of time or nodes the moves keep their one-ply evaluation
+        let evaluated_moves = self.sorted_root_moves(current_depth, i32::MIN, i32::MAX, control);
+        let mut result = if control.stopped() {
+            let scored = self
+                .evaluation_scored_moves()
+                .into_iter()
+                .map(|em| EvaluatedMove {
+                    pv: vec![em.mv],
+                    ..em
+                })
+                .collect();
+            search_result(scored, multi_pv, 1, control.nodes(), seed, &mut rng)
+        } else {
+            search_result(
+                evaluated_moves,
+                multi_pv,
+                current_depth,
+                control.nodes(),
+                seed,
+                &mut rng,
+            )
+        };
+        on_iteration(&result);
+        current_depth += 2;
 
         // Window size parameters
         let mut window_size = 1; // Initial window size
 
         // Main iterative deepening loop
-        while current_depth <= max_depth && start.elapsed().as_secs() < time_limit_secs {
-            // println!(
-            //     "Searching at depth {} with window around {}",
-            //     current_depth, best_score
-            // );
-
+        'deepening: while current_depth <= max_depth && !control.stopped() && !control.out_of_time()
+        {
             // Set aspiration window bounds
-            let mut alpha = best_score - window_size;
-            let mut beta = best_score + window_size;
-            let mut retry = true;
+            let (mut alpha, mut beta) = if multi_pv > 1 {
+                (i32::MIN, i32::MAX)
+            } else {
+                (result.score - window_size, result.score + window_size)
+            };
 
             // Try search with current window, expand if needed
-            while retry {
-                retry = false;
-                let mut nodes_evaluated = 0u64;
-
+            loop {
                 // Score each first-level move with the current window
-                let mut scored: Vec<EvaluatedMove> = self
-                    .evaluation_sorted_moves(0)
-                    .into_iter()
-                    .map(|mv| {
-                        self.make_move(mv, false, true);
-                        let sc = self.minimax_evaluate(
-                            current_depth - 1,
-                            alpha,
-                            beta,
-                            &mut nodes_evaluated,
-                            0,
-                        );
-                        self.undo_move();
-                        EvaluatedMove::new(mv, sc)
-                    })
-                    .collect();
-
-                // If score is outside window bounds, retry with wider window
-                if !scored.is_empty() {
-                    scored.sort();
-                    if self.blue_turn {
-                        scored.reverse();
-                    }
+                let scored = self.sorted_root_moves(current_depth, alpha, beta, control);
+                if control.stopped() {
+                    // keep the result of the last completed iteration
+                    break 'deepening;
+                }
 
-                    let new_score = scored[0].ev;
+                let new_score = scored[0].ev;
 
-                    // Check if result was outside the window
-                    if new_score <= alpha {
-                        // Failed low, retry with wider window
-                        // println!("Failed low: {} <= {}, widening window", new_score, alpha);
-                        window_size *= 2;
-                        alpha = new_score - window_size;
-                        retry = true;
-                        continue;
-                    } else if new_score >= beta {
-                        // Failed high, retry with wider window
-                        // println!("Failed high: {} >= {}, widening window", new_score, beta);
-                        window_size *= 2;
-                        beta = new_score + window_size;
-                        retry = true;
-                        continue;
+                // Check if result was outside the window
+                // a won or lost game opens that side of the window completely,
+                // doubling would take many re-searches to get there
+                if new_score <= alpha {
+                    // Failed low, retry with wider window
+                    window_size *= 2;
+                    alpha = if new_score <= -WIN_SCORE {
+                        i32::MIN
                     } else {
-                        // Search succeeded within window
-                        best_score = new_score;
-
-                        // Pick randomly among best-scoring moves
-                        let best_moves: Vec<Move> = scored
-                            .iter()
-                            .filter(|em| em.ev == new_score)
-                            .map(|em| em.mv)
-                            .collect();
-
-                        let mut rng = rand::rng();
-                        best_move = best_moves[rng.random_range(0..best_moves.len())];
-
-                        // Diagnostics
-                        // println!("Top 5 moves:");
-                        // for em in scored.iter().take(5) {
-                        //     println!("  {:?}", em);
-                        // }
-                        // println!("Nodes evaluated: {}", nodes_evaluated);
-                        // println!("Elapsed time: {:?}", start.elapsed());
-                    }
+                        new_score - window_size
+                    };
+                } else if new_score >= beta {
+                    // Failed high, retry with wider window
+                    window_size *= 2;
+                    beta = if new_score >= WIN_SCORE {
+                        i32::MAX
+                    } else {
+                        new_score + window_size
+                    };
+                } else {
+                    // Search succeeded within window
+                    result = search_result(
+                        scored,
+                        multi_pv,
+                        current_depth,
+                        control.nodes(),
+                        seed,
+                        &mut rng,
+                    );
+                    break;
                 }
             }
 
+            on_iteration(&result);
+
             // Reset window size for next iteration
             window_size = 1;
 
@@ -875,21 +1456,64 @@ impl Game {
             current_depth += 2;
         }
 
-        // println!("Final best move: {:?} with score {}", best_move, best_score);
-        EvaluatedMove::new(best_move, best_score)
+        result.nodes = control.nodes();
+        result
+    }
+
+    fn solved_lines(
+        &mut self,
+        control: &SearchControl,
+        multi_pv: usize,
+    ) -> Option<(Vec<EvaluatedMove>, ProvenResult)> {
+        // the best move solved exactly, or every move for several lines, with
+        // half of the time and nodes left so a failed solve leaves the
+        // heuristic search the other half
+        let max_nodes = control
+            .nodes_left()
+            .map_or(SOLVER_MAX_NODES, |left| (left / 2).min(SOLVER_MAX_NODES));
+        let max_time = control.time_left().map(|left| left / 2);
+        let solutions = if multi_pv > 1 {
+            self.solve_moves(control, max_nodes, max_time)?
+        } else {
+            vec![self.solve(control, max_nodes, max_time)?]
+        };
+
+        let mut lines: Vec<(EvaluatedMove, ProvenResult)> = solutions
+            .into_iter()
+            .map(|solution| {
+                let end = self.current_move_index + solution.pv.len();
+                let line = EvaluatedMove {
+                    mv: solution.pv[0],
+                    ev: terminal_score(solution.result.difference, end),
+                    pv: solution.pv,
+                };
+                (line, solution.result)
+            })
+            .collect();
+        lines.sort_by_key(|(line, _)| line.ev);
+        if self.blue_turn {
+            lines.reverse();
+        }
+        // moves with the same score end with the same difference
+        let proven = lines[0].1;
+        Some((lines.into_iter().map(|(line, _)| line).collect(), proven))
     }
 
     // Helper function to evaluate a specific move
-    fn evaluate_move(&mut self, mv: Move) -> i32 {
-        self.make_move(mv, false, true);
+    pub(crate) fn evaluate_move(&mut self, mv: Move) -> i32 {
+        self.push_move(mv);
         let score = self.evaluate();
-        self.undo_move();
+        self.pop_move();
         score
     }
 
     pub fn game_over(&mut self) -> bool {
         //     the game is over when the green player can't reach the blue player
-        self.reachable_with_cache(self.blue_position, self.height * self.height, true);
+        if let Some(bits) = &self.bitboard {
+            let blue_reachable = bits.reachable(self.blue_position, Bits::EMPTY, i32::MAX);
+            return !blue_reachable.get(self.green_position);
+        }
+        self.reachable_with_cache(self.blue_position, self.width * self.height, true);
         let blue_reachable = &mut self.blue_reachable_cache;
 
         if !blue_reachable.get(self.green_position) {
@@ -900,13 +1524,7 @@ impl Game {
 
     pub fn game_result(&mut self) -> (Winner, Score) {
         // the score is the area of the player can reach
-        self.reachable_with_cache(self.blue_position, self.height * self.height, true);
-        self.reachable_with_cache(self.green_position, self.height * self.height, true);
-        let blue_reachable = &mut self.blue_reachable_cache;
-        let green_reachable = &mut self.green_reachable_cache;
-
-        let blue_score = blue_reachable.total() as i32;
-        let green_score = green_reachable.total() as i32;
+        let (blue_score, green_score) = self.reachable_areas();
 
         let score = Score {
             blue: blue_score,
@@ -920,3 +1538,591 @@ impl Game {
         }
     }
 }
+
+fn search_result(
+    mut scored: Vec<EvaluatedMove>,
+    multi_pv: usize,
+    depth: i32,
+    nodes: u64,
+    seed: u64,
+    rng: &mut StdRng,
+) -> SearchResult {
+    // `scored` is sorted best first, pick randomly among the best-scoring moves
+    let ties = scored.iter().take_while(|em| em.ev == scored[0].ev).count();
+    scored.swap(0, rng.random_range(0..ties));
+    scored.truncate(multi_pv);
+
+    SearchResult {
+        best_move: scored[0].mv,
+        score: scored[0].ev,
+        depth,
+        nodes,
+        pv: scored[0].pv.clone(),
+        lines: scored,
+        proven: None,
+        seed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::difficulty::Difficulty;
+
+    // everything a move changes, leaving out the scratch caches and the search tables
+    #[derive(Debug, PartialEq)]
+    struct State {
+        blue_position: Coordinate,
+        green_position: Coordinate,
+        horizontal_walls: Board<Cell>,
+        vertical_walls: Board<Cell>,
+        bitboard: Option<WallBits>,
+        blue_turn: bool,
+        history: Vec<Move>,
+        current_move_index: usize,
+        hash: u64,
+    }
+
+    fn state(game: &Game) -> State {
+        State {
+            blue_position: game.blue_position,
+            green_position: game.green_position,
+            horizontal_walls: game.horizontal_walls.clone(),
+            vertical_walls: game.vertical_walls.clone(),
+            bitboard: game.bitboard.clone(),
+            blue_turn: game.blue_turn,
+            history: game.history.clone(),
+            current_move_index: game.current_move_index,
+            hash: game.hash,
+        }
+    }
+
+    // a board size, random moves to reach the start position and random
+    // moves played from there, each picking from the legal moves by index
+    fn random_game() -> impl Strategy<Value = ((i32, i32), Vec<usize>, Vec<usize>)> {
+        let size = (1..=12i32, 1..=12i32).prop_filter("two cells", |&(w, h)| w * h > 1);
+        (
+            size,
+            prop::collection::vec(any::<usize>(), 0..8),
+            prop::collection::vec(any::<usize>(), 0..60),
+        )
+    }
+
+    fn play(game: &mut Game, choices: &[usize]) {
+        for &choice in choices {
+            if game.game_over() {
+                break;
+            }
+            let moves = game.possible_moves();
+            game.make_move(moves[choice % moves.len()], true, true)
+                .unwrap();
+        }
+    }
+
+    fn start(size: (i32, i32), opening: &[usize]) -> Game {
+        let mut game = Game::new(size.0, size.1);
+        play(&mut game, opening);
+        Game::from_position(game.to_position())
+    }
+
+    // cells reachable from `start` over open edges, pawns do not block
+    fn naive_area(game: &Game, start: Coordinate) -> Vec<Coordinate> {
+        let mut seen = vec![start];
+        let mut next = 0;
+        while next < seen.len() {
+            let Coordinate { x, y } = seen[next];
+            next += 1;
+            let mut neighbours = Vec::new();
+            if x + 1 < game.width && game.vertical_walls.get(Coordinate::new(x, y)).is_empty() {
+                neighbours.push(Coordinate::new(x + 1, y));
+            }
+            if x > 0
+                && game
+                    .vertical_walls
+                    .get(Coordinate::new(x - 1, y))
+                    .is_empty()
+            {
+                neighbours.push(Coordinate::new(x - 1, y));
+            }
+            if y + 1 < game.height && game.horizontal_walls.get(Coordinate::new(x, y)).is_empty() {
+                neighbours.push(Coordinate::new(x, y + 1));
+            }
+            if y > 0
+                && game
+                    .horizontal_walls
+                    .get(Coordinate::new(x, y - 1))
+                    .is_empty()
+            {
+                neighbours.push(Coordinate::new(x, y - 1));
+            }
+            for cell in neighbours {
+                if !seen.contains(&cell) {
+                    seen.push(cell);
+                }
+            }
+        }
+        seen
+    }
+
+    fn check_against_naive(game: &mut Game) -> Result<(), TestCaseError> {
+        let blue_area = naive_area(game, game.blue_position);
+        let green_area = naive_area(game, game.green_position);
+        prop_assert_eq!(game.game_over(), !blue_area.contains(&game.green_position));
+
+        let (winner, score) = game.game_result();
+        prop_assert_eq!(score.blue, blue_area.len() as i32);
+        prop_assert_eq!(score.green, green_area.len() as i32);
+        let expected = match blue_area.len().cmp(&green_area.len()) {
+            std::cmp::Ordering::Greater => Winner::Blue,
+            std::cmp::Ordering::Less => Winner::Green,
+            std::cmp::Ordering::Equal => Winner::Draw,
+        };
+        prop_assert_eq!(winner, expected);
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn make_and_undo_restore_the_game((size, opening, choices) in random_game()) {
+            let mut game = start(size, &opening);
+            for choice in choices {
+                if game.game_over() {
+                    break;
+                }
+                let before = state(&game);
+                let moves = game.possible_moves();
+                let mv = moves[choice % moves.len()];
+                game.make_move(mv, true, true).unwrap();
+                let after = state(&game);
+                prop_assert_eq!(after.hash, game.compute_hash());
+
+                prop_assert_eq!(game.undo_move().unwrap(), mv);
+                prop_assert_eq!(state(&game), before);
+                game.make_move(mv, true, true).unwrap();
+                prop_assert_eq!(state(&game), after);
+            }
+        }
+
+        #[test]
+        fn jumping_equals_replaying(
+            (size, opening, choices) in random_game(),
+            jumps in prop::collection::vec(any::<usize>(), 1..8),
+        ) {
+            let mut game = start(size, &opening);
+            play(&mut game, &choices);
+            let latest = state(&game);
+            let history = game.history.clone();
+
+            for jump in jumps {
+                let index = jump % (history.len() + 1);
+                game.set_current_move_index(index).unwrap();
+
+                let mut replayed = Game::from_position(game.start_position().clone());
+                for &mv in &history[..index] {
+                    replayed.make_move(mv, true, true).unwrap();
+                }
+                let expected = State {
+                    history: history.clone(),
+                    ..state(&replayed)
+                };
+                prop_assert_eq!(state(&game), expected);
+                prop_assert_eq!(game.game_over(), replayed.game_over());
+            }
+
+            game.set_current_move_index(history.len()).unwrap();
+            prop_assert_eq!(state(&game), latest);
+        }
+
+        #[test]
+        fn game_over_and_result_match_a_flood_fill((size, opening, choices) in random_game()) {
+            let mut game = start(size, &opening);
+            check_against_naive(&mut game)?;
+            for choice in choices {
+                if game.game_over() {
+                    break;
+                }
+                play(&mut game, &[choice]);
+                check_against_naive(&mut game)?;
+                check_against_naive(&mut game.clone().without_bitboard())?;
+            }
+        }
+
+        #[test]
+        fn portable_code_matches_the_bitboard((size, opening, choices) in random_game()) {
+            let mut game = start(size, &opening);
+            let mut choices = choices.into_iter();
+            loop {
+                let mut portable = game.clone().without_bitboard();
+                prop_assert_eq!(game.possible_moves(), portable.possible_moves());
+                prop_assert_eq!(game.game_over(), portable.game_over());
+                prop_assert_eq!(game.reachable_areas(), portable.reachable_areas());
+                prop_assert_eq!(game.territory(), portable.territory());
+                if game.game_over() {
+                    break;
+                }
+                prop_assert_eq!(game.evaluation_features(), portable.evaluation_features());
+                let Some(choice) = choices.next() else {
+                    break;
+                };
+                play(&mut game, &[choice]);
+            }
+        }
+    }
+
+    #[test]
+    fn non_positive_depth_scores_the_moves_statically() {
+        let mut game = Game::new(7, 7);
+        let scores = |scored: Vec<EvaluatedMove>| -> Vec<(Move, i32)> {
+            scored.into_iter().map(|em| (em.mv, em.ev)).collect()
+        };
+        let one_ply = scores(game.minimax_evaluate_moves(1, &mut 0));
+        for depth in [0, -3] {
+            let mut nodes = 0;
+            let scored = scores(game.minimax_evaluate_moves(depth, &mut nodes));
+            assert_eq!(scored, one_ply);
+            assert_eq!(nodes, one_ply.len() as u64);
+        }
+    }
+
+    const SEARCH_POSITION: &str = "7x7 e5 c4 2b4/4b2/3b3/3b3/2g2g1/7 6/6/1g4/2gg2/4b1/6/6 b";
+
+    fn depth_limits(depth: i32) -> SearchLimits {
+        SearchLimits {
+            move_time_ms: None,
+            max_depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    #[test]
+    fn parallel_search_keeps_its_table() {
+        let mut game = Game::from_position_string(SEARCH_POSITION).unwrap();
+        game.parallel_search = true;
+        game.endgame_solver = false;
+        let first = game.iterative_deepening_minimax(&depth_limits(4));
+        let again = game.iterative_deepening_minimax(&depth_limits(4));
+        assert_eq!(again.score, first.score);
+        assert!(
+            again.nodes < first.nodes / 2,
+            "{} nodes after {}",
+            again.nodes,
+            first.nodes
+        );
+    }
+
+    #[test]
+    fn heuristic_ordering_saves_nodes() {
+        let nodes = |ordering: MoveOrdering| {
+            let mut game = Game::from_position_string(SEARCH_POSITION).unwrap();
+            game.parallel_search = false;
+            game.move_ordering = ordering;
+            game.iterative_deepening_minimax(&depth_limits(4)).nodes
+        };
+        let (plain, heuristic) = (nodes(MoveOrdering::Static), nodes(MoveOrdering::Heuristic));
+        assert!(
+            heuristic * 5 < plain * 4,
+            "{} nodes against {}",
+            heuristic,
+            plain
+        );
+    }
+
+    #[test]
+    fn both_orderings_find_the_same_best_move() {
+        let search = |ordering: MoveOrdering| {
+            let mut game = Game::from_position_string(SEARCH_POSITION).unwrap();
+            game.parallel_search = false;
+            game.late_move_reductions = false;
+            game.move_ordering = ordering;
+            game.seed = Some(2); // the same pick among equally scored moves
+            let result = game.iterative_deepening_minimax(&depth_limits(3));
+            (result.best_move, result.score)
+        };
+        assert_eq!(
+            search(MoveOrdering::Static),
+            search(MoveOrdering::Heuristic)
+        );
+    }
+
+    #[test]
+    fn the_first_iteration_keeps_to_the_limits() {
+        let mut game = Game::new(13, 13);
+        game.parallel_search = false;
+        let limits = SearchLimits {
+            max_nodes: Some(100),
+            ..depth_limits(8)
+        };
+        let result = game.iterative_deepening_minimax(&limits);
+        assert!(result.nodes <= 100, "{} nodes", result.nodes);
+        assert_eq!(result.depth, 1);
+        assert!(game.possible_moves().contains(&result.best_move));
+
+        let control = SearchControl::unlimited();
+        control.stop();
+        let result = game.iterative_deepening_search(&depth_limits(8), &control, |_| {});
+        assert_eq!(result.nodes, 0);
+        assert!(game.possible_moves().contains(&result.best_move));
+    }
+
+    #[test]
+    fn appended_moves_leave_the_view_alone() {
+        let mut game = Game::new(5, 5);
+        play(&mut game, &[3, 7, 1]);
+        game.set_current_move_index(1).unwrap();
+        let viewed = state(&game);
+
+        let mut latest = game.clone();
+        latest.set_current_move_index(3).unwrap();
+        let mv = latest.possible_moves()[5];
+        game.append_move(mv).unwrap();
+        assert_eq!(game.history.len(), 4);
+        assert_eq!(
+            state(&game),
+            State {
+                history: game.history.clone(),
+                ..viewed
+            }
+        );
+
+        // onto the other pawn in the latest position
+        latest.make_move(mv, true, true).unwrap();
+        let blocked = Move {
+            destination: latest.green_position,
+            place_wall: Direction::Up,
+        };
+        assert!(latest.blue_turn);
+        assert!(game.append_move(blocked).is_err());
+        assert_eq!(game.history.len(), 4);
+        assert_eq!(game.current_move_index, 1);
+    }
+
+    #[test]
+    fn solved_endgames_fill_in_every_line() {
+        let mut game = Game::from_position_string("3x3 a1 c3 3/3 2/2/2 b").unwrap();
+        game.seed = Some(1);
+        let limits = SearchLimits {
+            multi_pv: 3,
+            ..depth_limits(6)
+        };
+        let result = game.iterative_deepening_minimax(&limits);
+        assert_eq!(result.proven.unwrap().to_string(), "Green wins by 1");
+        assert_eq!(result.lines.len(), 3);
+        for line in &result.lines {
+            assert_eq!(line.ev, result.score);
+            assert_eq!(line.pv[0], line.mv);
+        }
+    }
+
+    #[test]
+    fn a_failed_solve_leaves_the_search_its_share() {
+        let mut game = Game::from_position_string("3x3 a1 c3 3/3 2/2/2 b").unwrap();
+        let limits = SearchLimits {
+            max_nodes: Some(2000),
+            ..depth_limits(6)
+        };
+        let result = game.iterative_deepening_minimax(&limits);
+        assert!(result.proven.is_none());
+        assert!(result.nodes <= 2000, "{} nodes", result.nodes);
+        assert!(result.depth > 1);
+    }
+
+    #[test]
+    fn mcts_stops_at_a_finished_game() {
+        // a1R walls the pawns apart
+        let mut game = Game::from_position_string("3x1 a1 c1 - 2 b").unwrap();
+        let limits = SearchLimits {
+            move_time_ms: None,
+            max_nodes: Some(100),
+            ..SearchLimits::default()
+        };
+        let config = crate::mcts::MctsConfig::default();
+        assert!(game.mcts(&limits, &config).is_some());
+        game.make_move(Move::from_notation("a1R").unwrap(), true, true)
+            .unwrap();
+        assert!(game.game_over());
+        assert!(game.mcts(&limits, &config).is_none());
+    }
+
+    fn search_lines(game: &mut Game, limits: &SearchLimits) -> Vec<(Move, i32)> {
+        let result = game.iterative_deepening_minimax(limits);
+        result.lines.iter().map(|em| (em.mv, em.ev)).collect()
+    }
+
+    #[test]
+    fn parallel_search_matches_the_sequential_one() {
+        // deep enough for late move reductions below the root moves, which
+        // the parallel search leaves out
+        let limits = SearchLimits {
+            multi_pv: 4,
+            ..depth_limits(5)
+        };
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        for (position, runs) in [
+            ("3x3 a1 c3 3/3 2/2/2 b", 3),
+            (
+                "7x7 b5 e2 2b1g2/1b1bb2/2bb3/3g3/2gg1g1/7 6/6/b2g2/bgbgg1/b3g1/6/6 b",
+                1,
+            ),
+        ] {
+            let mut game = Game::from_position_string(position).unwrap();
+            game.endgame_solver = false;
+            game.seed = Some(5); // the same pick among equally scored moves
+            assert!(game.late_move_reductions);
+
+            let mut sequential = game.clone();
+            sequential.parallel_search = false;
+            sequential.late_move_reductions = false;
+            let expected = search_lines(&mut sequential, &limits);
+            for _ in 0..runs {
+                let mut parallel = game.clone();
+                parallel.parallel_search = true;
+                let result = pool.install(|| search_lines(&mut parallel, &limits));
+                assert_eq!(result, expected, "{}", position);
+            }
+        }
+    }
+
+    #[test]
+    fn seeded_parallel_searches_repeat() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let position = "7x7 b5 e2 2b1g2/1b1bb2/2bb3/3g3/2gg1g1/7 6/6/b2g2/bgbgg1/b3g1/6/6 b";
+        let mut game = Game::from_position_string(position).unwrap();
+        game.parallel_search = true;
+        game.endgame_solver = false;
+        game.seed = Some(7);
+        let limits = SearchLimits {
+            multi_pv: 3,
+            ..depth_limits(3)
+        };
+
+        let search = |game: &Game| {
+            let result = pool.install(|| game.clone().iterative_deepening_minimax(&limits));
+            let lines: Vec<(Move, i32, Vec<Move>)> = result
+                .lines
+                .into_iter()
+                .map(|em| (em.mv, em.ev, em.pv))
+                .collect();
+            (result.best_move, lines, result.nodes, result.seed)
+        };
+        assert_eq!(search(&game), search(&game));
+
+        for level in [Difficulty::Beginner, Difficulty::Medium] {
+            let first = pool.install(|| game.clone().bot_move(level));
+            let again = pool.install(|| game.clone().bot_move(level));
+            assert_eq!(first, again, "{:?}", level);
+        }
+    }
+
+    #[test]
+    fn eval_weights_reach_the_search_score() {
+        let mut game = Game::from_position_string(SEARCH_POSITION).unwrap();
+        game.seed = Some(3);
+        let plain = game.iterative_deepening_minimax(&depth_limits(3));
+        let doubled = EvalWeights {
+            territory: 2.0,
+            ..EvalWeights::default()
+        };
+        game.eval_weights = doubled.clone();
+        let weighted = game.iterative_deepening_minimax(&depth_limits(3));
+        assert_eq!(weighted.score, 2 * plain.score);
+        // the table filled under other weights is not reused
+        game.eval_weights = EvalWeights::default();
+        let again = game.iterative_deepening_minimax(&depth_limits(3));
+        assert_eq!(again.score, plain.score);
+
+        let limits = SearchLimits {
+            move_time_ms: None,
+            max_nodes: Some(200),
+            ..SearchLimits::default()
+        };
+        let config = crate::mcts::MctsConfig {
+            playout: crate::mcts::PlayoutPolicy::Evaluation,
+            ..Default::default()
+        };
+        let plain = game.mcts(&limits, &config).unwrap();
+        game.eval_weights = doubled;
+        let weighted = game.mcts(&limits, &config).unwrap();
+        assert_ne!(weighted.score, plain.score);
+    }
+
+    #[test]
+    fn searches_leave_an_earlier_shown_position_alone() {
+        let mut game = Game::new(5, 5);
+        game.seed = Some(1);
+        for notation in ["b2U", "d4D", "b3R", "d3L"] {
+            let mv = Move::from_notation_on_board(notation, 5, 5).unwrap();
+            game.make_move(mv, true, true).unwrap();
+        }
+        game.set_current_move_index(1).unwrap();
+        let shown = game.to_position_string();
+        let history = game.history.clone();
+        let legal = game.possible_moves();
+
+        let limits = SearchLimits {
+            max_nodes: Some(200),
+            ..depth_limits(3)
+        };
+        let best = game.iterative_deepening_minimax(&limits).best_move;
+        assert!(legal.contains(&best));
+        let scored = game.minimax_evaluate_moves(2, &mut 0);
+        assert_eq!(scored.len(), legal.len());
+        let config = crate::mcts::MctsConfig::default();
+        assert!(legal.contains(&game.mcts(&limits, &config).unwrap().best_move));
+        assert!(legal.contains(&game.bot_move(Difficulty::Easy)));
+        game.perft_divide(2);
+
+        assert_eq!(game.to_position_string(), shown);
+        assert_eq!(game.history, history);
+        assert_eq!(game.current_move_index, 1);
+        assert_eq!(game.hash, game.compute_hash());
+    }
+    #[test]
+    fn rejected_moves_leave_the_game_alone() {
+        let mut game = Game::new(5, 5);
+        assert_eq!(game.undo_move(), Err(GameError::NothingToUndo));
+        for notation in ["b2U", "d4D"] {
+            let mv = Move::from_notation_on_board(notation, 5, 5).unwrap();
+            game.make_move(mv, true, true).unwrap();
+        }
+        let before = state(&game);
+
+        let rejected = [
+            ("e5U", GameError::IllegalDestination(Coordinate::new(4, 4))),
+            ("b2U", GameError::WallAlreadyPresent),
+            ("a2L", GameError::WallOnBorder),
+        ];
+        for (notation, error) in rejected {
+            let mv = Move::from_notation_on_board(notation, 5, 5).unwrap();
+            assert_eq!(game.make_move(mv, true, true), Err(error));
+            assert_eq!(state(&game), before);
+        }
+        let outside = Move::new(Coordinate::new(5, 0), Direction::Up);
+        assert_eq!(
+            game.make_move(outside, true, true),
+            Err(GameError::IllegalDestination(outside.destination))
+        );
+        assert_eq!(
+            game.set_current_move_index(3),
+            Err(GameError::MoveIndexOutOfRange(3))
+        );
+        assert_eq!(state(&game), before);
+
+        game.set_current_move_index(1).unwrap();
+        let shown = state(&game);
+        let legal = game.possible_moves()[0];
+        assert_eq!(
+            game.make_move(legal, true, true),
+            Err(GameError::NotLatestPosition)
+        );
+        assert_eq!(game.undo_move(), Err(GameError::NotLatestPosition));
+        assert_eq!(state(&game), shown);
+    }
+}
@@ -0,0 +1,234 @@
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::Deserialize;
+
+use crate::game::{EvaluatedMove, Game, Move, SearchResult};
+use crate::search::{SearchControl, SearchLimits};
+
+const DEFAULT_EXPLORATION: f64 = 1.4;
+const DEFAULT_PLAYOUT_MOVES: u32 = 20;
+const GREEDY_CANDIDATES: usize = 3; // random moves compared by the greedy playout
+const EVALUATION_SCALE: f64 = 4.0; // evaluation worth a 73% expected result, 4 cells by default
+
+/// How a new node of the tree is scored.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum PlayoutPolicy {
+    /// Uniformly random moves until the game ends or `playout_moves` run out.
+    Random,
+    /// Each move is the best of a few random ones by one-ply evaluation.
+    #[default]
+    Greedy,
+    /// No playout, the static evaluation of the new node itself.
+    Evaluation,
+}
+
+/// Tuning of the Monte Carlo tree search, its budget comes from `SearchLimits`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MctsConfig {
+    pub exploration: f64, // UCT exploration constant
+    pub playout: PlayoutPolicy,
+    pub playout_moves: u32, // unfinished playouts are scored by evaluation after this many moves
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            exploration: DEFAULT_EXPLORATION,
+            playout: PlayoutPolicy::default(),
+            playout_moves: DEFAULT_PLAYOUT_MOVES,
+        }
+    }
+}
+
+struct Node {
+    mv: Option<Move>, // None for the root
+    children: Vec<usize>,
+    untried: Option<Vec<Move>>, // filled when the node is first selected
+    visits: u32,
+    reward: f64, // summed results from the view of the side that played `mv`
+}
+
+impl Node {
+    fn new(mv: Option<Move>) -> Node {
+        Node {
+            mv,
+            children: Vec::new(),
+            untried: None,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        self.reward / self.visits as f64
+    }
+}
+
+// expected result for blue, 1 for a blue win, 0 for a green win
+fn outcome(difference: i32) -> f64 {
+    match difference.signum() {
+        1 => 1.0,
+        -1 => 0.0,
+        _ => 0.5,
+    }
+}
+
+fn evaluation_outcome(evaluation: i32) -> f64 {
+    1.0 / (1.0 + (-evaluation as f64 / EVALUATION_SCALE).exp())
+}
+
+impl Game {
+    /// Monte Carlo tree search with UCT selection, an alternative to
+    /// `iterative_deepening_minimax`. `limits.max_nodes` caps the number of
+    /// iterations, each adding one node to the tree.
+    ///
+    /// Scores are blue's expected result scaled to -1000 (green wins) ..
+    /// 1000 (blue wins), `depth` is the deepest node of the tree and the
+    /// lines follow the most visited children. `None` once the game is over.
+    pub fn mcts(&mut self, limits: &SearchLimits, config: &MctsConfig) -> Option<SearchResult> {
+        if self.game_over() {
+            return None;
+        }
+        if !self.is_showing_latest() {
+            return self.cut_to_shown().mcts(limits, config);
+        }
+        let control = SearchControl::from_limits(limits);
+        let (seed, mut rng) = self.search_rng();
+        let root_blue = self.blue_turn;
+        let mut tree = vec![Node::new(None)];
+        let mut max_depth = 0;
+
+        // at least one iteration, so the root has a move to return
+        while tree[0].children.is_empty() || !control.visit() {
+            // selection, down through fully expanded nodes
+            let mut path = vec![0];
+            let mut node = 0;
+            while !self.game_over() {
+                let untried = tree[node]
+                    .untried
+                    .get_or_insert_with(|| self.possible_moves());
+                if !untried.is_empty() {
+                    // expansion
+                    let mv = untried.swap_remove(rng.random_range(0..untried.len()));
+                    self.push_move(mv);
+                    let child = tree.len();
+                    tree.push(Node::new(Some(mv)));
+                    tree[node].children.push(child);
+                    path.push(child);
+                    break;
+                }
+
+                let log_visits = (tree[node].visits as f64).ln();
+                let uct = |child: &Node| {
+                    child.mean() + config.exploration * (log_visits / child.visits as f64).sqrt()
+                };
+                node = *tree[node]
+                    .children
+                    .iter()
+                    .max_by(|&&a, &&b| uct(&tree[a]).total_cmp(&uct(&tree[b])))
+                    .expect("a fully expanded node of a running game has children");
+                self.push_move(tree[node].mv.expect("only the root has no move"));
+                path.push(node);
+            }
+            max_depth = max_depth.max(path.len() - 1);
+
+            let blue_result = self.playout(config, &mut rng);
+            for _ in 1..path.len() {
+                self.pop_move();
+            }
+
+            // backpropagation, the side that played into path[i] alternates
+            for (i, &n) in path.iter().enumerate() {
+                let mover_blue = (i % 2 == 1) == root_blue;
+                tree[n].visits += 1;
+                tree[n].reward += if mover_blue {
+                    blue_result
+                } else {
+                    1.0 - blue_result
+                };
+            }
+        }
+
+        let most_visited = |node: usize| {
+            tree[node]
+                .children
+                .iter()
+                .copied()
+                .max_by_key(|&child| tree[child].visits)
+        };
+        let mut children = tree[0].children.clone();
+        children.sort_by_key(|&child| std::cmp::Reverse(tree[child].visits));
+        children.truncate(limits.multi_pv.max(1));
+        let lines: Vec<EvaluatedMove> = children
+            .into_iter()
+            .map(|child| {
+                let mut pv = vec![tree[child].mv.expect("only the root has no move")];
+                let mut node = child;
+                while let Some(next) = most_visited(node) {
+                    pv.extend(tree[next].mv);
+                    node = next;
+                }
+                let blue_mean = if root_blue {
+                    tree[child].mean()
+                } else {
+                    1.0 - tree[child].mean()
+                };
+                let ev = ((2.0 * blue_mean - 1.0) * 1000.0).round() as i32;
+                EvaluatedMove { mv: pv[0], ev, pv }
+            })
+            .collect();
+
+        Some(SearchResult {
+            best_move: lines[0].mv,
+            score: lines[0].ev,
+            depth: max_depth as i32,
+            nodes: control.nodes(),
+            pv: lines[0].pv.clone(),
+            lines,
+            proven: None,
+            seed,
+        })
+    }
+
+    // play the rest of the game (or some of it) from here, blue's expected result
+    fn playout(&mut self, config: &MctsConfig, rng: &mut impl Rng) -> f64 {
+        let mut played = 0;
+        let result = loop {
+            if self.game_over() {
+                let (blue, green) = self.reachable_areas();
+                break outcome(blue - green);
+            }
+            if played == config.playout_moves || config.playout == PlayoutPolicy::Evaluation {
+                break evaluation_outcome(self.static_evaluation());
+            }
+
+            let moves = self.possible_moves();
+            let mv = match config.playout {
+                PlayoutPolicy::Greedy => {
+                    let blue = self.blue_turn;
+                    let candidates: Vec<Move> = moves
+                        .choose_multiple(rng, GREEDY_CANDIDATES)
+                        .copied()
+                        .collect();
+                    let scored = candidates
+                        .into_iter()
+                        .map(|mv| (self.evaluate_move(mv), mv));
+                    let best = if blue {
+                        scored.max_by_key(|&(score, _)| score)
+                    } else {
+                        scored.min_by_key(|&(score, _)| score)
+                    };
+                    best.expect("a running game has moves").1
+                }
+                _ => *moves.choose(rng).expect("a running game has moves"),
+            };
+            self.push_move(mv);
+            played += 1;
+        };
+        for _ in 0..played {
+            self.pop_move();
+        }
+        result
+    }
+}
@@ -0,0 +1,204 @@
+// Square and move notation.
+//
+// Columns are letters counted like spreadsheet columns (a..z, aa..az, ba..),
+// rows are 1-based decimal numbers, so `c3` is (2, 2) and `ab12` is (27, 11).
+// A move is the destination followed by the wall direction, e.g. `c3R`.
+// Boards up to 26x9 get the same single-character notation as before.
+
+use crate::error::GameError;
+use crate::game::{Coordinate, Direction, Move};
+
+pub fn column_name(x: i32) -> String {
+    let mut letters = Vec::new();
+    let mut n = x as i64 + 1;
+    while n > 0 {
+        n -= 1;
+        letters.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).expect("column names are ascii")
+}
+
+fn parse_column(letters: &str) -> Option<i32> {
+    let mut n: i32 = 0;
+    for c in letters.bytes() {
+        n = n.checked_mul(26)?.checked_add((c - b'a') as i32 + 1)?;
+    }
+    Some(n - 1)
+}
+
+fn parse_row(digits: &str) -> Option<i32> {
+    // no sign and no leading zero, so every row has exactly one spelling
+    if digits.starts_with('0') {
+        return None;
+    }
+    Some(digits.parse::<i32>().ok()? - 1)
+}
+
+// length of the leading run of bytes matching `pred`
+fn prefix_len(s: &str, pred: impl Fn(u8) -> bool) -> usize {
+    s.bytes().take_while(|&b| pred(b)).count()
+}
+
+impl Coordinate {
+    pub fn to_notation(self) -> String {
+        format!("{}{}", column_name(self.x), self.y + 1)
+    }
+
+    pub fn from_notation(notation: &str) -> Result<Coordinate, GameError> {
+        let bad_notation = || GameError::BadNotation(notation.to_string());
+        let letters = prefix_len(notation, |b| b.is_ascii_lowercase());
+        let digits = prefix_len(&notation[letters..], |b| b.is_ascii_digit());
+        if letters == 0 || digits == 0 || letters + digits != notation.len() {
+            return Err(bad_notation());
+        }
+        let x = parse_column(&notation[..letters]).ok_or_else(bad_notation)?;
+        let y = parse_row(&notation[letters..]).ok_or_else(bad_notation)?;
+        Ok(Coordinate::new(x, y))
+    }
+}
+
+impl Direction {
+    pub fn from_notation(c: char) -> Option<Direction> {
+        match c {
+            'U' => Some(Direction::Up),
+            'D' => Some(Direction::Down),
+            'L' => Some(Direction::Left),
+            'R' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+impl Move {
+    pub fn to_notation(self) -> String {
+        format!("{:?}", self)
+    }
+
+    pub fn from_notation(notation: &str) -> Result<Move, GameError> {
+        let bad_notation = || GameError::BadNotation(notation.to_string());
+        let mut chars = notation.chars();
+        let place_wall = chars
+            .next_back()
+            .and_then(Direction::from_notation)
+            .ok_or_else(bad_notation)?;
+        let destination = Coordinate::from_notation(chars.as_str()).map_err(|_| bad_notation())?;
+        Ok(Move::new(destination, place_wall))
+    }
+
+    /// Like `from_notation`, also rejecting destinations off a `width` x `height` board.
+    pub fn from_notation_on_board(
+        notation: &str,
+        width: i32,
+        height: i32,
+    ) -> Result<Move, GameError> {
+        let mv = Move::from_notation(notation)?;
+        if !mv.destination.inside(width, height) {
+            return Err(GameError::BadNotation(notation.to_string()));
+        }
+        Ok(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::game::DIRECION_VALUES;
+
+    #[test]
+    fn small_boards_keep_single_character_notation() {
+        assert_eq!(Coordinate::new(0, 0).to_notation(), "a1");
+        assert_eq!(Coordinate::new(25, 8).to_notation(), "z9");
+        assert_eq!(
+            Move::new(Coordinate::new(2, 2), Direction::Right).to_notation(),
+            "c3R"
+        );
+    }
+
+    #[test]
+    fn large_coordinates() {
+        assert_eq!(Coordinate::new(26, 9).to_notation(), "aa10");
+        assert_eq!(Coordinate::new(27, 11).to_notation(), "ab12");
+        assert_eq!(Coordinate::new(701, 0).to_notation(), "zz1");
+        assert_eq!(Coordinate::new(702, 0).to_notation(), "aaa1");
+        assert_eq!(
+            Coordinate::from_notation("ab12"),
+            Ok(Coordinate::new(27, 11))
+        );
+    }
+
+    #[test]
+    fn round_trip_every_square_of_many_boards() {
+        for (width, height) in [(1, 2), (7, 7), (9, 9), (10, 10), (26, 9), (27, 12), (60, 3)] {
+            for y in 0..height {
+                for x in 0..width {
+                    for direction in DIRECION_VALUES {
+                        let mv = Move::new(Coordinate::new(x, y), direction);
+                        let notation = mv.to_notation();
+                        assert_eq!(
+                            Move::from_notation_on_board(&notation, width, height),
+                            Ok(mv),
+                            "{}",
+                            notation
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_random_coordinates() {
+        let mut rng = StdRng::seed_from_u64(8);
+        for _ in 0..10_000 {
+            let max = if rng.random_bool(0.5) {
+                1000
+            } else {
+                i32::MAX - 1
+            };
+            let mv = Move::new(
+                Coordinate::new(rng.random_range(0..max), rng.random_range(0..max)),
+                DIRECION_VALUES[rng.random_range(0..4)],
+            );
+            assert_eq!(Move::from_notation(&mv.to_notation()), Ok(mv));
+        }
+    }
+
+    #[test]
+    fn random_strings_parse_back_to_themselves_or_fail() {
+        let alphabet: Vec<char> = "abyz019UDLRx- ".chars().collect();
+        let mut rng = StdRng::seed_from_u64(80);
+        for _ in 0..50_000 {
+            let len = rng.random_range(0..8);
+            let s: String = (0..len)
+                .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                .collect();
+            if let Ok(mv) = Move::from_notation(&s) {
+                assert_eq!(mv.to_notation(), s);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_bad_notation() {
+        for bad in [
+            "", "a", "a1", "1aR", "a0R", "a01R", "A1R", "a1X", "a1RR", "a-1R",
+        ] {
+            assert!(Move::from_notation(bad).is_err(), "{}", bad);
+        }
+        assert!(Move::from_notation("zzzzzzzzz1R").is_err());
+        assert!(Move::from_notation("a99999999999R").is_err());
+    }
+
+    #[test]
+    fn rejects_squares_off_the_board() {
+        assert!(Move::from_notation_on_board("g7R", 7, 7).is_ok());
+        assert!(Move::from_notation_on_board("h7R", 7, 7).is_err());
+        assert!(Move::from_notation_on_board("g8R", 7, 7).is_err());
+        assert!(Move::from_notation_on_board("j10U", 10, 10).is_ok());
+    }
+}
@@ -0,0 +1,194 @@
+use serde::Deserialize;
+
+use crate::game::Move;
+
+const KILLERS_PER_PLY: usize = 2;
+
+/// How `minimax_evaluate` orders the children of a node.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum MoveOrdering {
+    /// Hash move first, the rest by a one-ply static evaluation (none at depth 1).
+    Static,
+    /// Hash move, then killer moves, then the rest by history score.
+    #[default]
+    Heuristic,
+}
+
+/// Move the stored best move of a node, if it is among `moves`, to the front
+/// and leave the others in their order.
+pub fn hash_move_first(moves: &mut [Move], hash_move: Option<Move>) {
+    if let Some(index) = hash_move.and_then(|hm| moves.iter().position(|&mv| mv == hm)) {
+        moves[..=index].rotate_right(1);
+    }
+}
+
+/// Killer moves and history scores, kept from one iteration of the
+/// iterative deepening to the next.
+///
+/// The parallel search gives each root move its own copy, so the order of
+/// the moves does not depend on how the workers are scheduled.
+#[derive(Clone, Debug)]
+pub struct OrderingTables {
+    width: i32,
+    killers: Vec<[Option<Move>; KILLERS_PER_PLY]>, // indexed by game ply
+    history: Vec<i32>,                             // per side to move and move
+}
+
+impl OrderingTables {
+    pub fn new(width: i32, height: i32) -> OrderingTables {
+        let moves = (width * height * 4) as usize;
+        OrderingTables {
+            width,
+            killers: Vec::new(),
+            history: vec![0; 2 * moves],
+        }
+    }
+
+    fn history_index(&self, mv: Move, blue_turn: bool) -> usize {
+        let ((x, y), direction) = mv.to_flat();
+        let index = ((y * self.width + x) * 4 + direction) as usize;
+        if blue_turn {
+            index
+        } else {
+            index + self.history.len() / 2
+        }
+    }
+
+    /// Forget the killers and fade the history before a new search.
+    pub fn new_search(&mut self) {
+        self.killers.clear();
+        for score in self.history.iter_mut() {
+            *score /= 2;
+        }
+    }
+
+    /// `mv` refuted the position at `ply` searched to `depth`.
+    pub fn record_cutoff(&mut self, mv: Move, blue_turn: bool, ply: usize, depth: i32) {
+        let index = self.history_index(mv, blue_turn);
+        self.history[index] += depth * depth;
+        self.add_killer(mv, ply);
+    }
+
+    fn add_killer(&mut self, mv: Move, ply: usize) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; KILLERS_PER_PLY]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+    }
+
+    /// Take over what `searched`, a copy of `snapshot`, learned since it
+    /// was copied, as if its cutoffs had been recorded here.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn merge(&mut self, searched: &OrderingTables, snapshot: &OrderingTables) {
+        for ((score, after), before) in self
+            .history
+            .iter_mut()
+            .zip(&searched.history)
+            .zip(&snapshot.history)
+        {
+            *score += after - before;
+        }
+        for (ply, killers) in searched.killers.iter().enumerate() {
+            for &mv in killers.iter().rev().flatten() {
+                self.add_killer(mv, ply);
+            }
+        }
+    }
+
+    /// Killers first, in the order they were found, then by history score.
+    pub fn sort(&self, moves: &mut [Move], blue_turn: bool, ply: usize) {
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        moves.sort_by_cached_key(|&mv| {
+            match killers.iter().position(|&killer| killer == Some(mv)) {
+                Some(rank) => (rank as i32, 0),
+                None => {
+                    let score = self.history[self.history_index(mv, blue_turn)];
+                    (KILLERS_PER_PLY as i32, -score)
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Coordinate, Direction};
+
+    fn moves() -> Vec<Move> {
+        let mut moves = Vec::new();
+        for x in 0..3 {
+            for direction in [Direction::Up, Direction::Right] {
+                moves.push(Move::new(Coordinate::new(x, 1), direction));
+            }
+        }
+        moves
+    }
+
+    #[test]
+    fn the_hash_move_goes_first() {
+        let mut sorted = moves();
+        hash_move_first(&mut sorted, Some(moves()[4]));
+        let mut expected = moves();
+        expected.remove(4);
+        expected.insert(0, moves()[4]);
+        assert_eq!(sorted, expected);
+
+        // a hash move that is not among the moves changes nothing
+        let mut sorted = moves();
+        hash_move_first(
+            &mut sorted,
+            Some(Move::new(Coordinate::new(2, 2), Direction::Up)),
+        );
+        assert_eq!(sorted, moves());
+    }
+
+    #[test]
+    fn killers_go_before_quiet_moves() {
+        let mut tables = OrderingTables::new(3, 3);
+        let quiet = moves();
+        // a strong history score alone does not outrank a killer of the ply
+        tables.record_cutoff(quiet[1], true, 4, 6);
+        tables.record_cutoff(quiet[5], true, 2, 1);
+        tables.record_cutoff(quiet[3], true, 2, 1);
+
+        let mut sorted = quiet.clone();
+        tables.sort(&mut sorted, true, 2);
+        assert_eq!(&sorted[..3], &[quiet[3], quiet[5], quiet[1]]);
+
+        // killers belong to their ply
+        let mut sorted = quiet.clone();
+        tables.sort(&mut sorted, true, 4);
+        assert_eq!(sorted[0], quiet[1]);
+    }
+
+    #[test]
+    fn history_changes_the_order() {
+        let mut tables = OrderingTables::new(3, 3);
+        let quiet = moves();
+        let mut sorted = quiet.clone();
+        tables.sort(&mut sorted, true, 0);
+        assert_eq!(sorted, quiet);
+
+        // cutoffs at other plies leave no killers at ply 0
+        tables.record_cutoff(quiet[2], true, 1, 1);
+        tables.record_cutoff(quiet[4], true, 1, 3);
+        tables.record_cutoff(quiet[0], false, 1, 5);
+        tables.sort(&mut sorted, true, 0);
+        assert_eq!(&sorted[..3], &[quiet[4], quiet[2], quiet[0]]);
+
+        // the scores are kept per side
+        tables.sort(&mut sorted, false, 0);
+        assert_eq!(sorted[0], quiet[0]);
+
+        // and fade with each new search
+        tables.new_search();
+        tables.record_cutoff(quiet[2], true, 1, 3);
+        tables.sort(&mut sorted, true, 0);
+        assert_eq!(&sorted[..2], &[quiet[2], quiet[4]]);
+    }
+}
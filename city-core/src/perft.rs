@@ -0,0 +1,122 @@
+use crate::game::{Game, Move};
+
+impl Game {
+    /// Number of move sequences of exactly `depth` moves from the current
+    /// position, a finished game having no moves. Counts for known positions
+    /// validate `possible_moves`, `game_over` and making and undoing moves.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self.game_over() {
+            return 0;
+        }
+        if !self.is_showing_latest() {
+            return self.cut_to_shown().perft(depth);
+        }
+        let moves = self.possible_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut total = 0;
+        for mv in moves {
+            self.push_move(mv);
+            total += self.perft(depth - 1);
+            self.pop_move();
+        }
+        total
+    }
+
+    /// `perft` split by first move, to find where two move generators differ.
+    pub fn perft_divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 || self.game_over() {
+            return Vec::new();
+        }
+        if !self.is_showing_latest() {
+            return self.cut_to_shown().perft_divide(depth);
+        }
+        let mut counts = Vec::new();
+        for mv in self.possible_moves() {
+            self.push_move(mv);
+            counts.push((mv, self.perft(depth - 1)));
+            self.pop_move();
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // positions with their perft counts from depth 1 on
+    const REFERENCE: [(&str, &[u64]); 9] = [
+        ("3x3 a1 c3 3/3 2/2/2 b", &[22, 394, 5770, 64594]),
+        ("7x7 a1 g7 7/7/7/7/7/7 6/6/6/6/6/6/6 b", &[32, 1024, 49920]),
+        (
+            "7x7 e5 c4 2b4/4b2/3b3/3b3/2g2g1/7 6/6/1g4/2gg2/4b1/6/6 b",
+            &[61, 3488, 144299],
+        ),
+        (
+            "7x7 b5 e2 2b1g2/1b1bb2/2bb3/3g3/2gg1g1/7 6/6/b2g2/bgbgg1/b3g1/6/6 b",
+            &[42, 1834, 49307],
+        ),
+        // adjacent pawns, neither may pass the other
+        ("5x5 c3 d3 5/5/5/5 4/4/4/4/4 g", &[53, 3559, 148120]),
+        ("9x4 a1 i4 9/9/9 8/8/8/8 b", &[31, 961, 40393]),
+        (
+            "9x9 f3 f6 9/5b3/9/9/3ggg3/5g3/9/9 8/8/b7/2b2b2/3bbg2/8/7g/8/8 b",
+            &[73, 4156],
+        ),
+        // too wide for the bitboard
+        ("20x3 a2 t2 20/20 19/19/19 b", &[31, 961, 34999]),
+        ("3x1 a1 c1 - 2 b", &[3, 1, 0]),
+    ];
+
+    #[test]
+    fn reference_counts() {
+        for (position, counts) in REFERENCE {
+            let mut game = Game::from_position_string(position).unwrap();
+            for (depth, &count) in (1..).zip(counts) {
+                assert_eq!(game.perft(depth), count, "{} depth {}", position, depth);
+            }
+        }
+    }
+
+    #[test]
+    fn portable_code_matches_the_bitboard() {
+        for (position, counts) in REFERENCE {
+            let mut game = Game::from_position_string(position)
+                .unwrap()
+                .without_bitboard();
+            for (depth, &count) in (1..).zip(counts) {
+                assert_eq!(game.perft(depth), count, "{} depth {}", position, depth);
+            }
+        }
+    }
+
+    #[test]
+    fn divide_by_hand_on_a_single_row() {
+        // a1R and b1R wall the pawns apart, after b1L only c1L is left to green
+        let mut game = Game::from_position_string("3x1 a1 c1 - 2 b").unwrap();
+        let divide: Vec<(String, u64)> = game
+            .perft_divide(2)
+            .into_iter()
+            .map(|(mv, count)| (mv.to_notation(), count))
+            .collect();
+        let expected = [("a1R", 0), ("b1L", 1), ("b1R", 0)];
+        assert_eq!(
+            divide,
+            expected.map(|(mv, count)| (mv.to_string(), count)).to_vec()
+        );
+    }
+
+    #[test]
+    fn perft_leaves_the_position_unchanged() {
+        let position = REFERENCE[2].0;
+        let mut game = Game::from_position_string(position).unwrap();
+        game.perft(3);
+        assert_eq!(game.to_position_string(), position);
+        assert_eq!(game.hash, game.compute_hash());
+    }
+}
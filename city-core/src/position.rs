@@ -0,0 +1,301 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::GameError;
+use crate::game::{Board, Cell, Coordinate};
+
+/// Largest width and height read from text, larger boards would be
+/// allocated before anything else in the string is checked.
+pub const MAX_BOARD_SIZE: i32 = 256;
+
+/// A board size such as `7x7`, with at least two cells and neither side
+/// larger than `MAX_BOARD_SIZE`.
+pub fn parse_board_size(size: &str) -> Option<(i32, i32)> {
+    let (w, h) = size.split_once('x')?;
+    let (w, h) = (w.parse::<i32>().ok()?, h.parse::<i32>().ok()?);
+    let side = 1..=MAX_BOARD_SIZE;
+    (side.contains(&w) && side.contains(&h) && w * h > 1).then_some((w, h))
+}
+
+/// Everything needed to continue a game from a given point, without its history.
+///
+/// The text form has six space separated fields, e.g. the start of a 7x7 game is
+///
+/// `7x7 a1 g7 7/7/7/7/7/7 6/6/6/6/6/6/6 b`
+///
+/// 1. board size as `<width>x<height>`
+/// 2. blue pawn and 3. green pawn, in move notation
+/// 4. horizontal walls (the edge below each cell) and 5. vertical walls (the
+///    edge right of each cell), one row per `/` from the top, `b`/`g` for a
+///    wall owned by blue/green and a number for a run of empty edges
+/// 6. side to move, `b` or `g`
+#[derive(Clone, Debug)]
+pub struct Position {
+    pub width: i32,
+    pub height: i32,
+    pub blue_position: Coordinate,
+    pub green_position: Coordinate,
+    pub horizontal_walls: Board<Cell>,
+    pub vertical_walls: Board<Cell>,
+    pub blue_turn: bool,
+}
+
+impl Position {
+    pub fn initial(width: i32, height: i32) -> Position {
+        Position {
+            width,
+            height,
+            blue_position: Coordinate::new(0, 0), // the top-left corner
+            green_position: Coordinate::new(width - 1, height - 1), // bottom-right corner
+            horizontal_walls: Board::new(width, height - 1, Cell::Empty),
+            vertical_walls: Board::new(width - 1, height, Cell::Empty),
+            blue_turn: true,
+        }
+    }
+}
+
+fn write_walls(
+    f: &mut fmt::Formatter,
+    walls: &Board<Cell>,
+    width: i32,
+    height: i32,
+) -> fmt::Result {
+    for y in 0..height {
+        if y > 0 {
+            write!(f, "/")?;
+        }
+        let mut empty_run = 0;
+        for x in 0..width {
+            match walls.get(Coordinate::new(x, y)) {
+                Cell::Empty => empty_run += 1,
+                owner => {
+                    if empty_run > 0 {
+                        write!(f, "{}", empty_run)?;
+                        empty_run = 0;
+                    }
+                    let c = if matches!(owner, Cell::Blue) {
+                        'b'
+                    } else {
+                        'g'
+                    };
+                    write!(f, "{}", c)?;
+                }
+            }
+        }
+        if empty_run > 0 {
+            write!(f, "{}", empty_run)?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_walls(field: &str, width: i32, height: i32) -> Result<Board<Cell>, String> {
+    let mut walls = Board::new(width, height, Cell::Empty);
+    // a board with a single row or column has no walls of one kind
+    if width == 0 || height == 0 {
+        return match field {
+            "-" => Ok(walls),
+            _ => Err(format!(
+                "expected '-' for the empty wall board, got '{}'",
+                field
+            )),
+        };
+    }
+    let rows: Vec<&str> = field.split('/').collect();
+    if rows.len() != height as usize {
+        return Err(format!("expected {} wall rows in '{}'", height, field));
+    }
+
+    for (y, row) in rows.iter().enumerate() {
+        let mut x = 0;
+        let mut chars = row.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                'b' | 'g' => {
+                    if x >= width {
+                        return Err(format!("wall row '{}' is too long", row));
+                    }
+                    let owner = if c == 'b' { Cell::Blue } else { Cell::Green };
+                    walls.set(Coordinate::new(x, y as i32), owner);
+                    x += 1;
+                }
+                '1'..='9' => {
+                    let too_long = || format!("wall row '{}' is too long", row);
+                    let mut run = c.to_digit(10).unwrap() as i32;
+                    while let Some(d) = chars.peek().and_then(|d| d.to_digit(10)) {
+                        run = run
+                            .checked_mul(10)
+                            .and_then(|run| run.checked_add(d as i32))
+                            .ok_or_else(too_long)?;
+                        chars.next();
+                    }
+                    x = x
+                        .checked_add(run)
+                        .filter(|&x| x <= width)
+                        .ok_or_else(too_long)?;
+                }
+                _ => return Err(format!("unexpected '{}' in wall row '{}'", c, row)),
+            }
+        }
+        if x != width {
+            return Err(format!("wall row '{}' should cover {} edges", row, width));
+        }
+    }
+    Ok(walls)
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} {} {} ",
+            self.width,
+            self.height,
+            self.blue_position.to_notation(),
+            self.green_position.to_notation()
+        )?;
+        if self.height > 1 {
+            write_walls(f, &self.horizontal_walls, self.width, self.height - 1)?;
+        } else {
+            write!(f, "-")?;
+        }
+        write!(f, " ")?;
+        if self.width > 1 {
+            write_walls(f, &self.vertical_walls, self.width - 1, self.height)?;
+        } else {
+            write!(f, "-")?;
+        }
+        write!(f, " {}", if self.blue_turn { 'b' } else { 'g' })
+    }
+}
+
+impl FromStr for Position {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Position, GameError> {
+        let bad_position = |reason: String| GameError::BadPosition(format!("{} ({})", reason, s));
+
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(bad_position(format!(
+                "expected 6 fields, got {}",
+                fields.len()
+            )));
+        }
+
+        let (width, height) = parse_board_size(fields[0])
+            .ok_or_else(|| bad_position(format!("bad board size '{}'", fields[0])))?;
+
+        let pawn = |field: &str| {
+            Coordinate::from_notation(field)
+                .ok()
+                .filter(|c| c.inside(width, height))
+                .ok_or_else(|| bad_position(format!("bad pawn square '{}'", field)))
+        };
+        let blue_position = pawn(fields[1])?;
+        let green_position = pawn(fields[2])?;
+        if blue_position == green_position {
+            return Err(bad_position("both pawns on the same square".to_string()));
+        }
+
+        let horizontal_walls = parse_walls(fields[3], width, height - 1).map_err(bad_position)?;
+        let vertical_walls = parse_walls(fields[4], width - 1, height).map_err(bad_position)?;
+
+        let blue_turn = match fields[5] {
+            "b" => true,
+            "g" => false,
+            other => return Err(bad_position(format!("bad side to move '{}'", other))),
+        };
+
+        Ok(Position {
+            width,
+            height,
+            blue_position,
+            green_position,
+            horizontal_walls,
+            vertical_walls,
+            blue_turn,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn initial_position_string() {
+        assert_eq!(
+            Position::initial(7, 7).to_string(),
+            "7x7 a1 g7 7/7/7/7/7/7 6/6/6/6/6/6/6 b"
+        );
+        assert_eq!(Position::initial(3, 1).to_string(), "3x1 a1 c1 - 2 b");
+    }
+
+    #[test]
+    fn round_trip_positions_of_random_games() {
+        let mut rng = StdRng::seed_from_u64(6);
+        for (width, height) in [(1, 2), (2, 1), (3, 1), (5, 5), (7, 7), (12, 4), (30, 11)] {
+            for _ in 0..20 {
+                let mut game = Game::new(width, height);
+                for _ in 0..rng.random_range(0..40) {
+                    if game.game_over() {
+                        break;
+                    }
+                    let moves = game.possible_moves();
+                    let mv = moves[rng.random_range(0..moves.len())];
+                    game.make_move(mv, true, true).unwrap();
+                }
+                let text = game.to_position_string();
+                let position: Position = text.parse().unwrap();
+                assert_eq!(position.to_string(), text);
+                assert_eq!(Game::from_position(position).to_position_string(), text);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_positions() {
+        for bad in [
+            "",
+            "7x7 a1 g7 7/7/7/7/7/7 6/6/6/6/6/6/6",
+            "7x7 a1 g7 7/7/7/7/7/7 6/6/6/6/6/6/6 b extra",
+            "7x7 a1 a1 7/7/7/7/7/7 6/6/6/6/6/6/6 b",
+            "7x7 a1 h7 7/7/7/7/7/7 6/6/6/6/6/6/6 b",
+            "7x7 a1 g7 7/7/7/7/7 6/6/6/6/6/6/6 b",
+            "7x7 a1 g7 7/7/7/7/7/6 6/6/6/6/6/6/6 b",
+            "7x7 a1 g7 7/7/7/7/7/8 6/6/6/6/6/6/6 b",
+            "7x7 a1 g7 7/7/7/7/7/6bb 6/6/6/6/6/6/6 b",
+            "7x7 a1 g7 7/7/7/7/7/0b6 6/6/6/6/6/6/6 b",
+            "7x7 a1 g7 7/7/7/7/7/x6 6/6/6/6/6/6/6 b",
+            "7x7 a1 g7 7/7/7/7/7/7 6/6/6/6/6/6/6 x",
+            "3x1 a1 c1 1 2 b",
+            "1x1 a1 a1 - - b",
+            "0x7 a1 a2 - 6/6/6/6/6/6/6 b",
+            "-7x7 a1 g7 7/7/7/7/7/7 6/6/6/6/6/6/6 b",
+            "2x2 a1 b2 99999999999 1/1 b",
+            "2x2 a1 b2 2147483647 1/1 b",
+            "2x2 a1 b2 1b2147483647 1/1 b",
+            "100000x100000 a1 b1 1 1 b",
+            "257x2 a1 b1 257 256/256 b",
+            "2x99999999999 a1 b1 2 1 b",
+        ] {
+            assert!(bad.parse::<Position>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn board_sizes() {
+        assert_eq!(parse_board_size("7x7"), Some((7, 7)));
+        assert_eq!(parse_board_size("256x1"), Some((256, 1)));
+        for bad in [
+            "1x1", "0x5", "5x0", "257x2", "7", "7x", "x7", "7x7x7", "-1x-9",
+        ] {
+            assert_eq!(parse_board_size(bad), None, "{}", bad);
+        }
+    }
+}
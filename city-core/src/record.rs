@@ -0,0 +1,436 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::error::GameError;
+use crate::game::{Game, Move, Winner};
+use crate::position::{Position, parse_board_size};
+
+// tags written first and in this order, any other tag follows alphabetically
+const STANDARD_TAGS: [&str; 10] = [
+    "Blue",
+    "Green",
+    "BlueElo",
+    "GreenElo",
+    "Date",
+    "Size",
+    "TimeControl",
+    "Result",
+    "Score",
+    "Position",
+];
+
+const LINE_WIDTH: usize = 80;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RecordedMove {
+    pub mv: Move,
+    pub comment: Option<String>,
+}
+
+/// A whole game in a PGN-like text format.
+///
+/// ```text
+/// [Blue "alice"]
+/// [Green "bob"]
+/// [Size "7x7"]
+/// [Result "1-0"]
+///
+/// 1. b1R f7U {blocks the corner} 2. c2D e6L 1-0
+/// ```
+///
+/// `Result` is `1-0` (blue wins), `0-1` (green wins), `1/2-1/2` or `*` for an
+/// unfinished game. A `Position` tag holds the position string when the game
+/// does not start from the initial position.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GameRecord {
+    pub tags: BTreeMap<String, String>,
+    pub moves: Vec<RecordedMove>,
+}
+
+pub fn result_token(winner: Option<Winner>) -> &'static str {
+    match winner {
+        Some(Winner::Blue) => "1-0",
+        Some(Winner::Green) => "0-1",
+        Some(Winner::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+fn is_result_token(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+impl GameRecord {
+    /// Record the whole history of `game`, with `Size`, `Result` and `Score`
+    /// filled in from the game itself.
+    pub fn from_game(game: &Game) -> GameRecord {
+        let mut record = GameRecord::default();
+        record.set_tag("Size", &format!("{}x{}", game.width, game.height));
+
+        let start = game.start_position();
+        if start.to_string() != Position::initial(game.width, game.height).to_string() {
+            record.set_tag("Position", &start.to_string());
+        }
+
+        // the result is the one of the latest position, not the displayed one
+        let mut latest = game.clone();
+        if !latest.is_showing_latest() {
+            latest
+                .set_current_move_index(latest.history.len())
+                .expect("the history length is a valid move index");
+        }
+        if latest.game_over() {
+            let (winner, score) = latest.game_result();
+            record.set_tag("Result", result_token(Some(winner)));
+            record.set_tag("Score", &format!("{}-{}", score.blue, score.green));
+        } else {
+            record.set_tag("Result", result_token(None));
+        }
+
+        record.moves = game
+            .history
+            .iter()
+            .map(|&mv| RecordedMove { mv, comment: None })
+            .collect();
+        record
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.get(name).map(String::as_str)
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        self.tags.insert(name.to_string(), value.to_string());
+    }
+
+    /// Replay the record, checking every move against the rules.
+    pub fn to_game(&self) -> Result<Game, GameError> {
+        let size = self.tag("Size").unwrap_or("7x7");
+        let (width, height) = parse_board_size(size)
+            .ok_or_else(|| GameError::BadRecord(format!("bad Size tag '{}'", size)))?;
+        let mut game = match self.tag("Position") {
+            Some(position) => Game::from_position_string(position)?,
+            None => Game::new(width, height),
+        };
+        // a Position tag without a Size tag decides the size by itself
+        if self.tag("Size").is_some() && (game.width, game.height) != (width, height) {
+            return Err(GameError::BadRecord(format!(
+                "Size tag '{}' does not match the Position tag",
+                size
+            )));
+        }
+        for recorded in &self.moves {
+            game.make_move(recorded.mv, true, true)?;
+        }
+        Ok(game)
+    }
+
+    pub fn parse(text: &str) -> Result<GameRecord, GameError> {
+        let mut record = GameRecord::default();
+        let mut lines = text.lines().peekable();
+
+        // tag section
+        while let Some(line) = lines.peek() {
+            let line = line.trim();
+            if line.is_empty() {
+                lines.next();
+                continue;
+            }
+            if !line.starts_with('[') {
+                break;
+            }
+            let (name, value) = parse_tag(line)?;
+            record.tags.insert(name, value);
+            lines.next();
+        }
+
+        // movetext
+        let movetext: Vec<&str> = lines.collect();
+        let movetext = movetext.join("\n");
+        let mut chars = movetext.chars().peekable();
+        let mut movetext_result = None;
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+            if c == '{' {
+                chars.next();
+                let comment: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let last = record.moves.last_mut().ok_or_else(|| {
+                    GameError::BadRecord("comment before the first move".to_string())
+                })?;
+                let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+                last.comment = Some(match last.comment.take() {
+                    Some(previous) => format!("{} {}", previous, comment),
+                    None => comment,
+                });
+                continue;
+            }
+
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '{' {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+
+            if movetext_result.is_some() {
+                return Err(GameError::BadRecord(format!(
+                    "'{}' after the game result",
+                    token
+                )));
+            }
+            if is_result_token(&token) {
+                movetext_result = Some(token);
+                continue;
+            }
+            // move numbers, "1." before blue and "1..." when green moves first
+            let mv = token.trim_start_matches(|c: char| c.is_ascii_digit());
+            let mv = if mv.len() < token.len() {
+                mv.trim_start_matches('.')
+            } else {
+                mv
+            };
+            if mv.is_empty() {
+                continue;
+            }
+            record.moves.push(RecordedMove {
+                mv: Move::from_notation(mv)?,
+                comment: None,
+            });
+        }
+
+        if let Some(result) = movetext_result {
+            match record.tag("Result") {
+                Some(tag) if tag != result => {
+                    return Err(GameError::BadRecord(format!(
+                        "movetext ends with {} but the Result tag is {}",
+                        result, tag
+                    )));
+                }
+                Some(_) => {}
+                None => record.set_tag("Result", &result),
+            }
+        }
+        Ok(record)
+    }
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), GameError> {
+    let bad_tag = || GameError::BadRecord(format!("bad tag line '{}'", line));
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(bad_tag)?;
+    let (name, value) = inner.split_once(char::is_whitespace).ok_or_else(bad_tag)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(bad_tag)?;
+
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next().ok_or_else(bad_tag)?),
+            '"' => return Err(bad_tag()),
+            c => unescaped.push(c),
+        }
+    }
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(bad_tag());
+    }
+    Ok((name.to_string(), unescaped))
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let standard = STANDARD_TAGS
+            .iter()
+            .filter_map(|&name| self.tags.get_key_value(name));
+        let others = self
+            .tags
+            .iter()
+            .filter(|(name, _)| !STANDARD_TAGS.contains(&name.as_str()));
+        for (name, value) in standard.chain(others) {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        // green moves first when the game starts from a position with green to move
+        let green_first = self
+            .tag("Position")
+            .and_then(|p| p.parse::<Position>().ok())
+            .is_some_and(|p| !p.blue_turn);
+
+        let mut tokens = Vec::new();
+        for (i, recorded) in self.moves.iter().enumerate() {
+            let ply = i + green_first as usize;
+            if ply.is_multiple_of(2) {
+                tokens.push(format!("{}.", ply / 2 + 1));
+            } else if i == 0 {
+                tokens.push(format!("{}...", ply / 2 + 1));
+            }
+            tokens.push(format!("{:?}", recorded.mv));
+            if let Some(comment) = &recorded.comment {
+                tokens.push(format!("{{{}}}", comment.replace(['{', '}'], "")));
+            }
+        }
+        tokens.push(self.tag("Result").unwrap_or("*").to_string());
+
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len > 0 && line_len + 1 + token.len() > LINE_WIDTH {
+                writeln!(f)?;
+                line_len = 0;
+            } else if line_len > 0 {
+                write!(f, " ")?;
+                line_len += 1;
+            }
+            write!(f, "{}", token)?;
+            line_len += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves_and_comments(record: &GameRecord) -> Vec<(Move, Option<String>)> {
+        record
+            .moves
+            .iter()
+            .map(|recorded| (recorded.mv, recorded.comment.clone()))
+            .collect()
+    }
+
+    // parse the text of `record` and check nothing was lost on the way
+    fn round_trip(record: &GameRecord) -> GameRecord {
+        let text = record.to_string();
+        let parsed = GameRecord::parse(&text).unwrap();
+        assert_eq!(parsed.tags, record.tags, "{}", text);
+        assert_eq!(moves_and_comments(&parsed), moves_and_comments(record));
+        assert_eq!(parsed.to_string(), text);
+        parsed
+    }
+
+    fn played_game(game: &mut Game, plies: usize) {
+        for i in 0..plies {
+            if game.game_over() {
+                break;
+            }
+            let moves = game.possible_moves();
+            game.make_move(moves[(i * 7) % moves.len()], true, true)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn round_trip_with_comments_and_escaped_tags() {
+        let mut game = Game::new(7, 7);
+        played_game(&mut game, 12);
+        let mut record = GameRecord::from_game(&game);
+        record.set_tag("Blue", "al\"ice\\");
+        record.set_tag("Event", "club {night} [2]");
+        record.moves[0].comment = Some("the usual start".to_string());
+        record.moves[5].comment = Some("a wall \"here\"".to_string());
+
+        let parsed = round_trip(&record);
+        assert_eq!(parsed.tag("Blue"), Some("al\"ice\\"));
+        assert_eq!(parsed.tag("Result"), Some("*"));
+        let replayed = parsed.to_game().unwrap();
+        assert_eq!(replayed.history, game.history);
+        assert_eq!(replayed.to_position_string(), game.to_position_string());
+    }
+
+    #[test]
+    fn comments_are_normalised() {
+        let text = "[Size \"5x5\"]\n\n1. b1R {two\n  lines}  {and more} d5L *\n";
+        let record = GameRecord::parse(text).unwrap();
+        assert_eq!(
+            record.moves[0].comment.as_deref(),
+            Some("two lines and more")
+        );
+        round_trip(&record);
+        assert!(GameRecord::parse("{before} 1. b1R *").is_err());
+    }
+
+    #[test]
+    fn green_moving_first() {
+        let mut opening = Game::new(5, 5);
+        played_game(&mut opening, 3);
+        let mut game = Game::from_position(opening.to_position());
+        assert!(!game.blue_turn);
+        played_game(&mut game, 5);
+        assert!(game.history.len() >= 2);
+
+        let record = GameRecord::from_game(&game);
+        let text = record.to_string();
+        assert!(text.contains("\n\n1... "), "{}", text);
+        assert!(text.contains(" 2. "), "{}", text);
+        let replayed = round_trip(&record).to_game().unwrap();
+        assert_eq!(replayed.history, game.history);
+        assert_eq!(
+            replayed.start_position().to_string(),
+            opening.to_position_string()
+        );
+    }
+
+    #[test]
+    fn finished_game_result() {
+        let mut game = Game::new(3, 1);
+        while !game.game_over() {
+            played_game(&mut game, 1);
+        }
+        let record = GameRecord::from_game(&game);
+        let parsed = round_trip(&record);
+        assert_ne!(parsed.tag("Result"), Some("*"));
+        assert!(parsed.to_game().unwrap().game_over());
+    }
+
+    #[test]
+    fn rejects_a_mismatched_result() {
+        let text = "[Size \"5x5\"]\n[Result \"1-0\"]\n\n1. b1R d5L 0-1\n";
+        assert!(matches!(
+            GameRecord::parse(text),
+            Err(GameError::BadRecord(_))
+        ));
+        assert!(GameRecord::parse("1. b1R 1-0 d5L").is_err());
+        let record = GameRecord::parse("1. b1R d5L 1/2-1/2").unwrap();
+        assert_eq!(record.tag("Result"), Some("1/2-1/2"));
+    }
+
+    #[test]
+    fn rejects_bad_sizes() {
+        for size in ["1x1", "0x7", "65536x65536", "99999999999x2", "300x300", "7"] {
+            let record = GameRecord::parse(&format!("[Size \"{}\"]\n\n*\n", size)).unwrap();
+            assert!(
+                matches!(record.to_game(), Err(GameError::BadRecord(_))),
+                "{}",
+                size
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_a_size_that_disagrees_with_the_position() {
+        let position = Game::new(5, 5).to_position_string();
+        let text = format!("[Size \"7x7\"]\n[Position \"{}\"]\n\n*\n", position);
+        let record = GameRecord::parse(&text).unwrap();
+        assert!(matches!(record.to_game(), Err(GameError::BadRecord(_))));
+
+        let record = GameRecord::parse(&text.replace("7x7", "5x5")).unwrap();
+        assert_eq!(record.to_game().unwrap().width, 5);
+        let record = GameRecord::parse(&format!("[Position \"{}\"]\n\n*\n", position)).unwrap();
+        assert_eq!(record.to_game().unwrap().width, 5);
+    }
+}
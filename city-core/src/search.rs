@@ -0,0 +1,199 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use serde::Deserialize;
+// std::time::Instant panics on wasm32-unknown-unknown, web_time falls back to performance.now()
+use web_time::{Duration, Instant};
+
+pub const MAX_SEARCH_DEPTH: i32 = 64;
+
+// A finished game scores WIN_SCORE plus MARGIN_WEIGHT per cell of difference,
+// less the number of moves played, negated when green wins. A bigger win
+// always ranks first and a quicker one breaks ties, while a lost side prefers
+// the smallest and latest loss. Every such score is beyond +-WIN_SCORE.
+pub const WIN_SCORE: i32 = 1_000_000;
+pub const MARGIN_WEIGHT: i32 = 1_000;
+
+// late move reductions: from the LMR_MIN_MOVE_INDEX-th child of a node with at
+// least LMR_MIN_DEPTH plies left, first search LMR_REDUCTION plies shallower.
+// Two plies keep the leaves on the same side to move as the full search.
+pub const LMR_MIN_DEPTH: i32 = 4;
+pub const LMR_MIN_MOVE_INDEX: usize = 3;
+pub const LMR_REDUCTION: i32 = 2;
+
+const DEFAULT_MOVE_TIME_MS: u64 = 3000;
+const MOVES_TO_GO: u64 = 20; // assumed number of moves left when playing on a clock
+const CLOCK_SAFETY_MS: u64 = 50;
+pub(crate) const TIME_CHECK_INTERVAL: u64 = 256; // nodes between two clock reads
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SearchLimits {
+    pub move_time_ms: Option<u64>, // fixed time for this move
+    pub remaining_ms: Option<u64>, // time left on the clock of the side to move
+    pub increment_ms: u64,         // added to the clock after each move
+    pub max_depth: Option<i32>,
+    pub max_nodes: Option<u64>,
+    pub multi_pv: usize, // number of best moves reported with their lines
+}
+
+/// Score of a game that ended with `difference` more cells for blue after
+/// `moves_played` moves. The move count only has to be consistent within a
+/// game, as each move places one wall it follows from the position.
+pub fn terminal_score(difference: i32, moves_played: usize) -> i32 {
+    let moves = (moves_played as i32).min(MARGIN_WEIGHT - 1);
+    let win = |margin: i32| WIN_SCORE + margin * MARGIN_WEIGHT - moves;
+    match difference.signum() {
+        1 => win(difference),
+        -1 => -win(-difference),
+        _ => 0,
+    }
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        SearchLimits {
+            move_time_ms: Some(DEFAULT_MOVE_TIME_MS),
+            remaining_ms: None,
+            increment_ms: 0,
+            max_depth: None,
+            max_nodes: None,
+            multi_pv: 1,
+        }
+    }
+}
+
+impl SearchLimits {
+    /// Time to spend on this move, `None` when the search is not timed.
+    pub fn time_budget(&self) -> Option<Duration> {
+        let from_clock = self.remaining_ms.map(|remaining| {
+            let usable = remaining.saturating_sub(CLOCK_SAFETY_MS);
+            (remaining / MOVES_TO_GO + self.increment_ms).min(usable)
+        });
+        let budget = match (self.move_time_ms, from_clock) {
+            (Some(move_time), Some(clock)) => Some(move_time.min(clock)),
+            (move_time, clock) => move_time.or(clock),
+        };
+        budget.map(Duration::from_millis)
+    }
+}
+
+/// Shared state of a running search: node count, deadline and the stop flag.
+///
+/// It is `Sync` so the parallel root search can share one instance across workers.
+#[derive(Debug)]
+pub struct SearchControl {
+    start: Instant,
+    deadline: Option<Instant>,
+    max_nodes: Option<u64>,
+    nodes: AtomicU64,
+    stopped: AtomicBool,
+}
+
+impl SearchControl {
+    pub fn new(time_budget: Option<Duration>, max_nodes: Option<u64>) -> SearchControl {
+        let start = Instant::now();
+        SearchControl {
+            start,
+            deadline: time_budget.map(|budget| start + budget),
+            max_nodes,
+            nodes: AtomicU64::new(0),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn unlimited() -> SearchControl {
+        SearchControl::new(None, None)
+    }
+
+    pub fn from_limits(limits: &SearchLimits) -> SearchControl {
+        SearchControl::new(limits.time_budget(), limits.max_nodes)
+    }
+
+    /// Count one node, return true if the search has to be abandoned.
+    pub fn visit(&self) -> bool {
+        let nodes = self.nodes.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.stopped() {
+            return true;
+        }
+        let out_of_nodes = self.max_nodes.is_some_and(|max| nodes >= max);
+        let out_of_time = nodes.is_multiple_of(TIME_CHECK_INTERVAL) && self.out_of_time();
+        if out_of_nodes || out_of_time {
+            self.stop();
+            return true;
+        }
+        false
+    }
+
+    pub fn out_of_time(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Time until the deadline, `None` when the search is not timed.
+    pub fn time_left(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Nodes until `max_nodes`, `None` without a node limit.
+    pub fn nodes_left(&self) -> Option<u64> {
+        self.max_nodes.map(|max| max.saturating_sub(self.nodes()))
+    }
+
+    /// Count nodes searched without `visit`, they do not stop the search.
+    pub fn add_nodes(&self, nodes: u64) {
+        self.nodes.fetch_add(nodes, Ordering::Relaxed);
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bigger_margins_score_higher() {
+        assert!(terminal_score(3, 20) > terminal_score(2, 20));
+        // even the slowest bigger win beats the quickest smaller one
+        assert!(terminal_score(2, 5000) > terminal_score(1, 0));
+        assert!(terminal_score(-3, 20) < terminal_score(-2, 20));
+    }
+
+    #[test]
+    fn quicker_wins_score_higher() {
+        assert!(terminal_score(2, 10) > terminal_score(2, 11));
+        assert!(terminal_score(-2, 10) < terminal_score(-2, 11));
+    }
+
+    #[test]
+    fn draws_score_zero() {
+        assert_eq!(terminal_score(0, 0), 0);
+        assert_eq!(terminal_score(0, 37), 0);
+    }
+
+    #[test]
+    fn wins_stay_beyond_win_score_for_both_sides() {
+        for difference in [1, 2, 25, 400] {
+            for moves in [0, 1, 48, 998, 999, 5000] {
+                let blue = terminal_score(difference, moves);
+                assert!(blue > WIN_SCORE, "{} {}", difference, moves);
+                assert_eq!(terminal_score(-difference, moves), -blue);
+            }
+        }
+    }
+}
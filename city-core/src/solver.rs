@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+use serde::ser::{SerializeStruct, Serializer};
+use web_time::{Duration, Instant};
+
+use crate::game::{Game, Move, Winner};
+use crate::search::{SearchControl, TIME_CHECK_INTERVAL};
+use crate::transposition::Bound;
+
+/// Largest number of cells still reachable by both pawns for which the
+/// search hands the position to the exact solver.
+pub const SOLVER_MAX_AREA: i32 = 16;
+
+/// Nodes the solver may spend before the position is left to the heuristic search.
+pub const SOLVER_MAX_NODES: u64 = 500_000;
+
+/// Outcome of a position under perfect play by both sides.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvenResult {
+    pub winner: Winner,
+    pub difference: i32, // blue cells minus green cells at the end of the game
+}
+
+impl ProvenResult {
+    pub fn from_difference(difference: i32) -> ProvenResult {
+        let winner = match difference.signum() {
+            1 => Winner::Blue,
+            -1 => Winner::Green,
+            _ => Winner::Draw,
+        };
+        ProvenResult { winner, difference }
+    }
+}
+
+impl fmt::Display for ProvenResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.winner {
+            Winner::Blue => write!(f, "Blue wins by {}", self.difference),
+            Winner::Green => write!(f, "Green wins by {}", -self.difference),
+            Winner::Draw => write!(f, "Draw"),
+        }
+    }
+}
+
+impl Serialize for ProvenResult {
+    // with the display text, so front ends do not have to build it
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ProvenResult", 3)?;
+        state.serialize_field("winner", &self.winner)?;
+        state.serialize_field("difference", &self.difference)?;
+        state.serialize_field("summary", &self.to_string())?;
+        state.end()
+    }
+}
+
+/// A proven result with the line that achieves it.
+#[derive(Clone, Debug, Serialize)]
+pub struct Solution {
+    pub result: ProvenResult,
+    pub pv: Vec<Move>, // best play to the end of the game, starts with the best move
+}
+
+#[derive(Clone, Copy)]
+struct SolverEntry {
+    score: i32,
+    bound: Bound,
+    best_move: Option<Move>,
+}
+
+struct Solver<'a> {
+    control: &'a SearchControl, // only to notice a stop
+    max_nodes: u64,
+    deadline: Option<Instant>,
+    nodes: u64,
+    aborted: bool,
+    // positions are solved to the end, so no depth is needed to reuse an entry
+    table: HashMap<u64, SolverEntry>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(control: &'a SearchControl, max_nodes: u64, max_time: Option<Duration>) -> Solver<'a> {
+        Solver {
+            control,
+            max_nodes,
+            deadline: max_time.map(|time| Instant::now() + time),
+            nodes: 0,
+            aborted: false,
+            table: HashMap::new(),
+        }
+    }
+
+    // count one node, true once the solver has to give up
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        let out_of_time = self.nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        if self.nodes > self.max_nodes || out_of_time || self.control.stopped() {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+}
+
+impl Game {
+    /// Cells both pawns can still reach, `None` once the game is over.
+    pub fn contested_area(&mut self) -> Option<i32> {
+        if self.game_over() {
+            return None;
+        }
+        Some(self.reachable_areas().0)
+    }
+
+    /// Search every line to the end of the game for the exact final
+    /// difference. `None` once the game is over, or when `control` is
+    /// stopped or `max_nodes` or `max_time` run out before the position is
+    /// solved. The nodes are added to `control`, but its own limits do not
+    /// apply to the solver.
+    pub fn solve(
+        &mut self,
+        control: &SearchControl,
+        max_nodes: u64,
+        max_time: Option<Duration>,
+    ) -> Option<Solution> {
+        if self.game_over() {
+            return None;
+        }
+        if !self.is_showing_latest() {
+            return self.cut_to_shown().solve(control, max_nodes, max_time);
+        }
+        let mut solver = Solver::new(control, max_nodes, max_time);
+        let mut pv = Vec::new();
+        let difference = self.solve_node(&mut solver, i32::MIN, i32::MAX, &mut pv);
+        control.add_nodes(solver.nodes);
+        if solver.aborted {
+            return None;
+        }
+        Some(Solution {
+            result: ProvenResult::from_difference(difference),
+            pv,
+        })
+    }
+
+    /// Like `solve`, the exact result of every move instead of the best
+    /// one, in the order of `possible_moves`. Each line starts with its move.
+    pub fn solve_moves(
+        &mut self,
+        control: &SearchControl,
+        max_nodes: u64,
+        max_time: Option<Duration>,
+    ) -> Option<Vec<Solution>> {
+        if self.game_over() {
+            return None;
+        }
+        if !self.is_showing_latest() {
+            return self
+                .cut_to_shown()
+                .solve_moves(control, max_nodes, max_time);
+        }
+        let mut solver = Solver::new(control, max_nodes, max_time);
+        let mut solutions = Vec::new();
+        for mv in self.possible_moves() {
+            let mut pv = Vec::new();
+            self.push_move(mv);
+            let difference = self.solve_node(&mut solver, i32::MIN, i32::MAX, &mut pv);
+            self.pop_move();
+            if solver.aborted {
+                break;
+            }
+            pv.insert(0, mv);
+            solutions.push(Solution {
+                result: ProvenResult::from_difference(difference),
+                pv,
+            });
+        }
+        control.add_nodes(solver.nodes);
+        (!solver.aborted).then_some(solutions)
+    }
+
+    fn solve_node(
+        &mut self,
+        solver: &mut Solver,
+        mut alpha: i32,
+        mut beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        if solver.visit() {
+            return 0;
+        }
+
+        if self.game_over() {
+            let (blue, green) = self.reachable_areas();
+            return blue - green;
+        }
+
+        let key = self.hash;
+        let hash_move = match solver.table.get(&key).copied() {
+            Some(entry) => {
+                match entry.bound {
+                    Bound::Exact => {
+                        self.table_line(&solver.table, pv);
+                        return entry.score;
+                    }
+                    Bound::Lower if entry.score >= beta => return entry.score,
+                    Bound::Upper if entry.score <= alpha => return entry.score,
+                    _ => {}
+                }
+                entry.best_move
+            }
+            None => None,
+        };
+
+        let mut moves = self.evaluation_sorted_moves(0);
+        if let Some(index) = hash_move.and_then(|hm| moves.iter().position(|&mv| mv == hm)) {
+            moves[..=index].rotate_right(1);
+        }
+
+        let (alpha_orig, beta_orig) = (alpha, beta);
+        let mut value = if self.blue_turn { i32::MIN } else { i32::MAX };
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        for mv in moves {
+            self.push_move(mv);
+            let score = self.solve_node(solver, alpha, beta, &mut child_pv);
+            self.pop_move();
+            if solver.aborted {
+                return 0;
+            }
+            let improved = if self.blue_turn {
+                score > value
+            } else {
+                score < value
+            };
+            if improved || best_move.is_none() {
+                value = score;
+                best_move = Some(mv);
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+            }
+            if self.blue_turn {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if value <= alpha_orig {
+            Bound::Upper
+        } else if value >= beta_orig {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        solver.table.insert(
+            key,
+            SolverEntry {
+                score: value,
+                bound,
+                best_move,
+            },
+        );
+        value
+    }
+
+    // the line stored for this position, as long as its entries are exact
+    fn table_line(&mut self, table: &HashMap<u64, SolverEntry>, line: &mut Vec<Move>) {
+        let start = line.len();
+        while let Some(SolverEntry {
+            bound: Bound::Exact,
+            best_move: Some(mv),
+            ..
+        }) = table.get(&self.hash).copied()
+        {
+            line.push(mv);
+            self.push_move(mv);
+        }
+        for _ in start..line.len() {
+            self.pop_move();
+        }
+    }
+}
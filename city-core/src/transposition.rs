@@ -0,0 +1,84 @@
+use crate::game::Move;
+
+pub const DEFAULT_TT_ENTRIES: usize = 1 << 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower, // the real score is at least `score` (fail high)
+    Upper, // the real score is at most `score` (fail low)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TtEntry {
+    pub key: u64,
+    pub depth: i32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+}
+
+/// Fixed size, always-indexed-by-key hash table of searched positions.
+///
+/// Storage is allocated on the first `store`, so games that never search
+/// do not pay for it.
+#[derive(Clone, Debug)]
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+    capacity: usize,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_TT_ENTRIES)
+    }
+}
+
+impl TranspositionTable {
+    /// `capacity` is rounded up to a power of two.
+    pub fn new(capacity: usize) -> TranspositionTable {
+        TranspositionTable {
+            entries: Vec::new(),
+            capacity: capacity.max(1).next_power_of_two(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.capacity - 1)
+    }
+
+    pub fn probe(&self, key: u64) -> Option<&TtEntry> {
+        self.entries
+            .get(self.index(key))?
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    pub fn store(&mut self, entry: TtEntry) {
+        if self.entries.is_empty() {
+            self.entries = vec![None; self.capacity];
+        }
+        let index = self.index(entry.key);
+        let slot = &mut self.entries[index];
+        // keep the deeper result when the same position is stored again
+        let replace = match slot {
+            Some(old) => old.key != entry.key || entry.depth >= old.depth,
+            None => true,
+        };
+        if replace {
+            *slot = Some(entry);
+        }
+    }
+
+    /// Store every entry of `other` here, in the order of its slots.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn merge(&mut self, other: &TranspositionTable) {
+        for entry in other.entries.iter().flatten() {
+            self.store(*entry);
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+use crate::difficulty::Difficulty;
+use crate::error::GameError;
+use crate::evaluation::EvalWeights;
+use crate::game::*;
+use crate::mcts::MctsConfig;
+use crate::record::GameRecord;
+use crate::search::SearchLimits;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_SEARCH_TYPES: &'static str = r#"
+export interface PlainMove {
+    destination: { x: number; y: number };
+    place_wall: "Up" | "Down" | "Left" | "Right";
+}
+
+export type GameErrorCode =
+    | "ILLEGAL_DESTINATION"
+    | "WALL_ALREADY_PRESENT"
+    | "WALL_ON_BORDER"
+    | "NOT_LATEST_POSITION"
+    | "NOTHING_TO_UNDO"
+    | "MOVE_INDEX_OUT_OF_RANGE"
+    | "BAD_NOTATION"
+    | "BAD_POSITION"
+    | "BAD_RECORD"
+    | "BAD_WEIGHTS";
+
+export interface GameRecord {
+    tags: Record<string, string>;
+    moves: { mv: PlainMove; comment: string | null }[];
+}
+
+export interface SearchLimits {
+    move_time_ms?: number;
+    remaining_ms?: number;
+    increment_ms?: number;
+    max_depth?: number;
+    max_nodes?: number;
+    multi_pv?: number;
+}
+
+export interface EvalWeights {
+    territory?: number;
+    mobility?: number;
+    pawn_distance?: number;
+    frontier?: number;
+    walls?: number;
+}
+
+export type Difficulty = "Beginner" | "Easy" | "Medium" | "Hard" | "Expert";
+
+export interface MctsConfig {
+    exploration?: number;
+    playout?: "Random" | "Greedy" | "Evaluation";
+    playout_moves?: number;
+}
+
+export interface SearchResult {
+    best_move: PlainMove;
+    score: number; // blue's view, beyond +-1000000 once the game is decided
+
+    depth: number;
+    nodes: number;
+    pv: PlainMove[];
+    lines: EvaluatedMove[];
+    proven: ProvenResult | null;
+    seed: number;
+}
+
+export interface ProvenResult {
+    winner: "Blue" | "Green" | "Draw";
+    difference: number;
+    summary: string;
+}
+
+export interface EvaluatedMove {
+    mv: PlainMove;
+    ev: number;
+    pv: PlainMove[];
+}
+"#;
+
+impl From<GameError> for JsValue {
+    // thrown as a JS `Error` carrying the variant in `error.code`
+    fn from(err: GameError) -> JsValue {
+        let js_err = js_sys::Error::new(&err.to_string());
+        js_sys::Reflect::set(&js_err, &"code".into(), &err.code().into())
+            .expect("setting a property on a fresh Error object");
+        js_err.into()
+    }
+}
+
+#[wasm_bindgen]
+pub fn parse_record(record: &str) -> Result<JsValue, JsValue> {
+    let record = GameRecord::parse(record)?;
+    Ok(serde_wasm_bindgen::to_value(&record).unwrap())
+}
+
+#[derive(Serialize)]
+pub struct GameResult {
+    winner: Winner,
+    score: Score,
+}
+#[wasm_bindgen]
+pub struct City {
+    inner: Game,
+}
+
+#[wasm_bindgen]
+impl City {
+    pub fn new(width: i32, height: i32) -> City {
+        City {
+            inner: Game::new(width, height),
+        }
+    }
+
+    pub fn from_position_string(position: &str) -> Result<City, JsValue> {
+        Ok(City {
+            inner: Game::from_position_string(position)?,
+        })
+    }
+
+    pub fn to_position_string(&self) -> String {
+        self.inner.to_position_string()
+    }
+
+    /// Replay a recorded game, the board shows its final position.
+    pub fn from_record(record: &str) -> Result<City, JsValue> {
+        Ok(City {
+            inner: GameRecord::parse(record)?.to_game()?,
+        })
+    }
+
+    /// Tags such as `{ Blue: "alice", Date: "2025.07.01" }` are added to
+    /// the generated `Size`, `Result` and `Score` tags.
+    pub fn to_record(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Record<string, string> | undefined")] tags: JsValue,
+    ) -> Result<String, JsValue> {
+        let mut record = GameRecord::from_game(&self.inner);
+        if !tags.is_undefined() && !tags.is_null() {
+            let tags: BTreeMap<String, String> = serde_wasm_bindgen::from_value(tags)?;
+            for (name, value) in tags {
+                record.set_tag(&name, &value);
+            }
+        }
+        Ok(record.to_string())
+    }
+
+    pub fn get_blue_position(&self) -> Coordinate {
+        self.inner.blue_position
+    }
+
+    pub fn get_green_position(&self) -> Coordinate {
+        self.inner.green_position
+    }
+
+    pub fn get_vertical_wall(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.inner.vertical_walls).unwrap()
+    }
+
+    pub fn get_horizontal_wall(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.inner.horizontal_walls).unwrap()
+    }
+
+    pub fn get_history(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.inner.history).unwrap()
+    }
+
+    pub fn blue_turn(&self) -> bool {
+        self.inner.blue_turn
+    }
+
+    pub fn make_move(&mut self, mv: Move, safe: bool) -> Result<(), JsValue> {
+        Ok(self.inner.make_move(mv, safe, true)?)
+    }
+
+    /// Add the opponent's move to the end of the game, the board stays on
+    /// the move the user is looking at.
+    pub fn append_move(&mut self, mv: Move) -> Result<(), JsValue> {
+        Ok(self.inner.append_move(mv)?)
+    }
+
+    pub fn possible_moves(&mut self) -> JsValue {
+        let moves = self.inner.possible_moves();
+        serde_wasm_bindgen::to_value(&moves).unwrap()
+    }
+
+    pub fn undo_move(&mut self) -> Result<(), JsValue> {
+        self.inner.undo_move()?;
+        Ok(())
+    }
+
+    pub fn game_over(&mut self) -> bool {
+        self.inner.game_over()
+    }
+
+    pub fn game_result(&mut self) -> JsValue {
+        let (winner, score) = self.inner.game_result();
+        let result = GameResult { winner, score };
+        serde_wasm_bindgen::to_value(&result).unwrap()
+    }
+
+    pub fn get_current_move_index(&self) -> usize {
+        self.inner.current_move_index
+    }
+
+    pub fn previous_move(&mut self) -> Result<(), JsValue> {
+        if self.inner.current_move_index > 0 {
+            self.inner
+                .set_current_move_index(self.inner.current_move_index - 1)?;
+        }
+        Ok(())
+    }
+
+    pub fn next_move(&mut self) -> Result<(), JsValue> {
+        if self.inner.current_move_index < self.inner.history.len() {
+            self.inner
+                .set_current_move_index(self.inner.current_move_index + 1)?;
+        }
+        Ok(())
+    }
+
+    pub fn first_move(&mut self) -> Result<(), JsValue> {
+        Ok(self.inner.set_current_move_index(0)?)
+    }
+
+    pub fn last_move(&mut self) -> Result<(), JsValue> {
+        Ok(self
+            .inner
+            .set_current_move_index(self.inner.history.len())?)
+    }
+
+    pub fn jump_to_move(&mut self, index: usize) -> Result<(), JsValue> {
+        Ok(self.inner.set_current_move_index(index)?)
+    }
+
+    pub fn is_showing_latest(&self) -> bool {
+        self.inner.is_showing_latest()
+    }
+
+    /// Fix the seed of the engine's random choices so searches and bot moves
+    /// repeat, `null` draws a new seed for every search. Every search result
+    /// reports the seed it used.
+    pub fn set_seed(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "number | null")] seed: JsValue,
+    ) -> Result<(), JsValue> {
+        self.inner.seed = serde_wasm_bindgen::from_value(seed)?;
+        Ok(())
+    }
+
+    /// Weights of the evaluation features for every later search, missing
+    /// weights are zero except territory. Throws `BAD_WEIGHTS` on unknown
+    /// fields.
+    pub fn set_eval_weights(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "EvalWeights")] weights: JsValue,
+    ) -> Result<(), JsValue> {
+        let weights: EvalWeights = serde_wasm_bindgen::from_value(weights)
+            .map_err(|e| GameError::BadWeights(e.to_string()))?;
+        self.inner.eval_weights = weights;
+        Ok(())
+    }
+
+    /// Best move for the side to move, or `undefined` once the game is over.
+    /// Missing limits fall back to a 3 second search. `pv` is the expected
+    /// line and `lines` holds the best `multi_pv` moves, each with its own line.
+    /// Small endgames are solved exactly, `proven.summary` then reads like
+    /// "Blue wins by 4".
+    #[wasm_bindgen(unchecked_return_type = "SearchResult | undefined")]
+    pub fn search(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "SearchLimits")] limits: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        if self.inner.game_over() {
+            return Ok(JsValue::UNDEFINED);
+        }
+        let limits: SearchLimits = if limits.is_undefined() || limits.is_null() {
+            SearchLimits::default()
+        } else {
+            serde_wasm_bindgen::from_value(limits)?
+        };
+        let result = self.inner.iterative_deepening_minimax(&limits);
+        Ok(serde_wasm_bindgen::to_value(&result).unwrap())
+    }
+
+    /// The bot's reply at the given difficulty, or `undefined` once the game
+    /// is over. Lower levels search shallowly and pick at random among good
+    /// moves, "Expert" runs the full 3 second search.
+    #[wasm_bindgen(unchecked_return_type = "PlainMove | undefined")]
+    pub fn bot_move(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "Difficulty")] level: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        if self.inner.game_over() {
+            return Ok(JsValue::UNDEFINED);
+        }
+        let level: Difficulty = serde_wasm_bindgen::from_value(level)?;
+        let mv = self.inner.bot_move(level);
+        Ok(serde_wasm_bindgen::to_value(&mv).unwrap())
+    }
+
+    /// Every legal move scored at a fixed depth with its line, best first, for hints.
+    #[wasm_bindgen(unchecked_return_type = "EvaluatedMove[]")]
+    pub fn evaluate_moves(&mut self, depth: i32) -> JsValue {
+        if self.inner.game_over() {
+            return serde_wasm_bindgen::to_value(&Vec::<EvaluatedMove>::new()).unwrap();
+        }
+        let moves = self.inner.minimax_evaluate_moves(depth, &mut 0u64);
+        serde_wasm_bindgen::to_value(&moves).unwrap()
+    }
+
+    /// Like `search`, but with Monte Carlo tree search. `limits.max_nodes`
+    /// caps the iterations and scores run from -1000 (green wins) to 1000
+    /// (blue wins). Missing settings fall back to the defaults.
+    #[wasm_bindgen(unchecked_return_type = "SearchResult | undefined")]
+    pub fn mcts_search(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "SearchLimits")] limits: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "MctsConfig")] config: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        if self.inner.game_over() {
+            return Ok(JsValue::UNDEFINED);
+        }
+        let limits: SearchLimits = if limits.is_undefined() || limits.is_null() {
+            SearchLimits::default()
+        } else {
+            serde_wasm_bindgen::from_value(limits)?
+        };
+        let config: MctsConfig = if config.is_undefined() || config.is_null() {
+            MctsConfig::default()
+        } else {
+            serde_wasm_bindgen::from_value(config)?
+        };
+        let result = self.inner.mcts(&limits, &config);
+        Ok(result.map_or(JsValue::UNDEFINED, |result| {
+            serde_wasm_bindgen::to_value(&result).unwrap()
+        }))
+    }
+}
@@ -0,0 +1,90 @@
+use crate::game::{Cell, Coordinate};
+
+// fixed seed so every Game of the same size shares the same keys
+const ZOBRIST_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Random keys xor-ed together to identify a position.
+///
+/// A position hash combines one key per pawn square, one key per wall
+/// (per owner) and one key for green to move.
+#[derive(Debug)]
+pub struct ZobristKeys {
+    width: i32,
+    blue_pawn: Vec<u64>,
+    green_pawn: Vec<u64>,
+    horizontal_walls: Vec<[u64; 2]>, // [blue owner, green owner]
+    vertical_walls: Vec<[u64; 2]>,
+    green_turn: u64,
+}
+
+impl ZobristKeys {
+    pub fn new(width: i32, height: i32) -> ZobristKeys {
+        let mut state = ZOBRIST_SEED ^ ((width as u64) << 32 | height as u64);
+        let cells = (width * height) as usize;
+        let mut keys = |n: usize| (0..n).map(|_| splitmix64(&mut state)).collect::<Vec<_>>();
+
+        let blue_pawn = keys(cells);
+        let green_pawn = keys(cells);
+        let mut wall_keys = |n: usize| {
+            (0..n)
+                .map(|_| [splitmix64(&mut state), splitmix64(&mut state)])
+                .collect::<Vec<_>>()
+        };
+        let horizontal_walls = wall_keys((width * (height - 1)).max(0) as usize);
+        let vertical_walls = wall_keys(((width - 1) * height).max(0) as usize);
+        let green_turn = splitmix64(&mut state);
+
+        ZobristKeys {
+            width,
+            blue_pawn,
+            green_pawn,
+            horizontal_walls,
+            vertical_walls,
+            green_turn,
+        }
+    }
+
+    pub fn blue_pawn(&self, position: Coordinate) -> u64 {
+        self.blue_pawn[(position.y * self.width + position.x) as usize]
+    }
+
+    pub fn green_pawn(&self, position: Coordinate) -> u64 {
+        self.green_pawn[(position.y * self.width + position.x) as usize]
+    }
+
+    // `position` indexes the horizontal wall board (the edge below the cell)
+    pub fn horizontal_wall(&self, position: Coordinate, owner: Cell) -> u64 {
+        Self::wall_key(
+            &self.horizontal_walls[(position.y * self.width + position.x) as usize],
+            owner,
+        )
+    }
+
+    // `position` indexes the vertical wall board (the edge right of the cell)
+    pub fn vertical_wall(&self, position: Coordinate, owner: Cell) -> u64 {
+        Self::wall_key(
+            &self.vertical_walls[(position.y * (self.width - 1) + position.x) as usize],
+            owner,
+        )
+    }
+
+    pub fn green_turn(&self) -> u64 {
+        self.green_turn
+    }
+
+    fn wall_key(keys: &[u64; 2], owner: Cell) -> u64 {
+        match owner {
+            Cell::Empty => 0,
+            Cell::Blue => keys[0],
+            Cell::Green => keys[1],
+        }
+    }
+}